@@ -43,20 +43,93 @@ pub struct PageCache<T: CacheValidator> {
     address: Box<[Address]>,
     cache: Box<[u8]>,
     address_once_validated: Box<[Address]>,
+    // per-slot dirty bit for write-back buffering
+    dirty: Box<[bool]>,
+    // when true, writes are buffered in the resident page and only flushed on
+    // eviction/`flush`; when false, writes are forwarded immediately
+    write_back: bool,
+    // dirty pages evicted while no `mem` handle is available are parked here
+    // until the next `flush`
+    pending: Vec<(Address, Box<[u8]>)>,
+    // per-slot monotonic access counter used to pick the least-recently-used
+    // victim within a set
+    lru: Box<[u64]>,
+    clock: u64,
+    // associativity: number of slots per set (1 == direct mapped)
+    ways: usize,
+    num_sets: usize,
     page_size: Length,
     page_type_mask: PageType,
+    // hit/miss/validation/eviction counters, see `stats`
+    stats: PageCacheStats,
+    // when `Some`, every physical read request is appended as `(address, length)`
+    // so a live workload can be captured and replayed offline
+    recording: Option<Vec<(Address, usize)>>,
     pub validator: T,
 }
 
+/// Hit/miss instrumentation for a [`PageCache`].
+///
+/// All counters are monotonic for the lifetime of the cache; take snapshots via
+/// [`PageCache::stats`] before and after a workload to measure it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PageCacheStats {
+    /// Lookups that resolved to a resident, valid page.
+    pub hits: u64,
+    /// Lookups that missed and fell through to the backing memory.
+    pub misses: u64,
+    /// Pages read from backing memory and validated into a slot.
+    pub validations: u64,
+    /// Valid pages overwritten to make room for another address.
+    pub evictions: u64,
+}
+
+impl PageCacheStats {
+    /// Fraction of lookups served from the cache, in `0.0..=1.0`.
+    ///
+    /// Returns `0.0` when no lookup has happened yet.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
 impl<T: CacheValidator> PageCache<T> {
+    /// Creates a direct-mapped page cache (one way per set).
     pub fn new(
         arch: Architecture,
         size: Length,
         page_type_mask: PageType,
+        validator: T,
+    ) -> Self {
+        Self::with_associativity(arch, size, page_type_mask, 1, validator)
+    }
+
+    /// Creates an `ways`-way set-associative page cache.
+    ///
+    /// With `ways == 1` this is the classic direct-mapped scheme. Higher
+    /// associativity removes conflict misses on workloads that walk a few
+    /// widely-spaced page tables, at the cost of a short linear scan bounded by
+    /// `ways` on every lookup.
+    pub fn with_associativity(
+        arch: Architecture,
+        size: Length,
+        page_type_mask: PageType,
+        ways: usize,
         mut validator: T,
     ) -> Self {
         let page_size = arch.page_size();
-        let cache_entries = size.as_usize() / page_size.as_usize();
+        let total_entries = (size.as_usize() / page_size.as_usize()).max(1);
+
+        // clamp associativity to the number of available slots and round the
+        // cache down to a whole number of sets
+        let ways = ways.max(1).min(total_entries);
+        let num_sets = (total_entries / ways).max(1);
+        let cache_entries = num_sets * ways;
 
         let layout =
             Layout::from_size_align(cache_entries * page_size.as_usize(), page_size.as_usize())
@@ -75,15 +148,125 @@ impl<T: CacheValidator> PageCache<T> {
             address: vec![Address::INVALID; cache_entries].into_boxed_slice(),
             cache,
             address_once_validated: vec![Address::INVALID; cache_entries].into_boxed_slice(),
+            dirty: vec![false; cache_entries].into_boxed_slice(),
+            write_back: false,
+            pending: Vec::new(),
+            lru: vec![0; cache_entries].into_boxed_slice(),
+            clock: 0,
+            ways,
+            num_sets,
             page_size,
             page_type_mask,
+            stats: PageCacheStats::default(),
+            recording: None,
             validator,
         }
     }
 
+    /// Returns a snapshot of the hit/miss/validation/eviction counters.
+    pub fn stats(&self) -> PageCacheStats {
+        self.stats
+    }
+
+    /// Starts (or restarts) recording the sequence of physical read requests.
+    ///
+    /// While recording, each `(address, length)` pair passed to the read paths
+    /// is appended to an in-memory trace. Capture a trace from a live target,
+    /// retrieve it with [`take_recording`](Self::take_recording) and replay it
+    /// offline against a `FileIoMemory`-backed snapshot to benchmark different
+    /// cache geometries.
+    pub fn record(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    /// Stops recording and returns the captured trace, if any.
+    pub fn take_recording(&mut self) -> Option<Vec<(Address, usize)>> {
+        self.recording.take()
+    }
+
+    /// Appends a request to the active recording, if one is running.
+    fn trace(&mut self, addr: Address, len: usize) {
+        if let Some(rec) = self.recording.as_mut() {
+            rec.push((addr, len));
+        }
+    }
+
+    /// Returns the first slot index of the set an address maps to.
+    fn set_base(&self, addr: Address) -> usize {
+        let set = (addr.as_page_aligned(self.page_size).as_usize() / self.page_size.as_usize())
+            % self.num_sets;
+        set * self.ways
+    }
+
+    /// Marks a slot as most-recently-used.
+    fn touch(&mut self, slot: usize) {
+        self.clock += 1;
+        self.lru[slot] = self.clock;
+    }
+
+    /// Returns the slot currently holding a valid copy of `addr`, if any,
+    /// bumping its LRU position.
+    fn lookup(&mut self, addr: Address) -> Option<usize> {
+        let aligned = addr.as_page_aligned(self.page_size);
+        let base = self.set_base(addr);
+        for slot in base..(base + self.ways) {
+            if self.address[slot] == aligned && self.validator.is_slot_valid(slot) {
+                self.touch(slot);
+                return Some(slot);
+            }
+        }
+        None
+    }
+
+    /// Chooses the slot to (re)use for `addr` within its set: an existing slot
+    /// already primed for this address, otherwise an invalid slot, otherwise the
+    /// least-recently-used slot.
+    fn victim(&mut self, addr: Address) -> usize {
+        let aligned = addr.as_page_aligned(self.page_size);
+        let base = self.set_base(addr);
+
+        // reuse the slot that was already primed for this address (so repeated
+        // misses resolve to the same buffer)
+        for slot in base..(base + self.ways) {
+            if self.address_once_validated[slot] == aligned {
+                return slot;
+            }
+        }
+
+        let mut victim = base;
+        let mut victim_key = (true, u64::MAX);
+        for slot in base..(base + self.ways) {
+            let key = (self.validator.is_slot_valid(slot), self.lru[slot]);
+            if key < victim_key {
+                victim_key = key;
+                victim = slot;
+            }
+        }
+
+        // recycling a still-valid slot holding a different page is a genuine
+        // eviction (a primed-slot reuse returned above, so this is not one)
+        if self.validator.is_slot_valid(victim) && self.address[victim] != aligned {
+            self.stats.evictions += 1;
+        }
+
+        // a dirty slot being recycled must not lose its buffered write; park it
+        // until the next flush
+        self.evict_dirty(victim);
+        victim
+    }
+
+    /// Parks a dirty slot's contents in `pending` and clears its dirty bit.
+    fn evict_dirty(&mut self, slot: usize) {
+        if self.dirty[slot] {
+            let page = self.page_from_index(slot).to_vec().into_boxed_slice();
+            self.pending.push((self.address[slot], page));
+            self.dirty[slot] = false;
+        }
+    }
+
+    #[allow(dead_code)]
     fn page_index(&self, addr: Address) -> usize {
-        (addr.as_page_aligned(self.page_size).as_usize() / self.page_size.as_usize())
-            % self.address.len()
+        self.set_base(addr)
     }
 
     fn page_and_info_from_index(&mut self, idx: usize) -> (&mut [u8], &mut Address, &mut Address) {
@@ -104,13 +287,16 @@ impl<T: CacheValidator> PageCache<T> {
         &mut self,
         addr: Address,
     ) -> std::result::Result<&mut [u8], (&mut [u8], &mut Address, &mut Address)> {
-        let page_index = self.page_index(addr);
-        if self.address[page_index] == addr.as_page_aligned(self.page_size)
-            && self.validator.is_slot_valid(page_index)
-        {
-            Ok(self.page_from_index(page_index))
-        } else {
-            Err(self.page_and_info_from_index(page_index))
+        match self.lookup(addr) {
+            Some(slot) => {
+                self.stats.hits += 1;
+                Ok(self.page_from_index(slot))
+            }
+            None => {
+                self.stats.misses += 1;
+                let slot = self.victim(addr);
+                Err(self.page_and_info_from_index(slot))
+            }
         }
     }
 
@@ -148,32 +334,68 @@ impl<T: CacheValidator> PageCache<T> {
 
     pub fn validate_page(&mut self, addr: Address, page_type: PageType) {
         if self.page_type_mask.contains(page_type) {
-            let idx = self.page_index(addr);
             let aligned_addr = addr.as_page_aligned(self.page_size);
+            // locate the slot that was primed for this address on the miss
+            let idx = self.slot_primed_for(aligned_addr);
             let page_info = self.page_and_info_from_index(idx);
             *page_info.1 = aligned_addr;
             self.validator.validate_slot(idx);
+            self.stats.validations += 1;
             debug_assert_eq!(self.address_once_validated[idx], aligned_addr);
             self.address_once_validated[idx] = Address::INVALID;
+            self.touch(idx);
         }
     }
 
     pub fn invalidate_page(&mut self, addr: Address, page_type: PageType) {
         if self.page_type_mask.contains(page_type) {
-            let idx = self.page_index(addr);
-            let page_info = self.page_and_info_from_index(idx);
-            *page_info.1 = Address::null();
-            self.validator.invalidate_slot(idx);
-            self.address_once_validated[idx] = Address::INVALID;
+            let aligned_addr = addr.as_page_aligned(self.page_size);
+            if let Some(idx) = self.slot_of(aligned_addr) {
+                // do not drop buffered writes on invalidation
+                self.evict_dirty(idx);
+                let page_info = self.page_and_info_from_index(idx);
+                *page_info.1 = Address::null();
+                self.validator.invalidate_slot(idx);
+                self.address_once_validated[idx] = Address::INVALID;
+            }
+        }
+    }
+
+    /// Enables (`true`) or disables (`false`, the default) write-back buffering.
+    ///
+    /// In write-back mode [`cached_write`](Self::cached_write) only updates the
+    /// resident page and defers the physical write until eviction or
+    /// [`flush`](Self::flush). In write-through mode the write is forwarded
+    /// immediately while the cached copy is kept coherent.
+    pub fn set_write_back(&mut self, write_back: bool) {
+        self.write_back = write_back;
+    }
+
+    /// Returns the slot within `addr`'s set that was primed for it (its
+    /// `address_once_validated` matches), falling back to the victim slot.
+    fn slot_primed_for(&mut self, aligned_addr: Address) -> usize {
+        let base = self.set_base(aligned_addr);
+        for slot in base..(base + self.ways) {
+            if self.address_once_validated[slot] == aligned_addr {
+                return slot;
+            }
         }
+        self.victim(aligned_addr)
+    }
+
+    /// Returns the slot currently caching `addr` (valid or not), if any.
+    fn slot_of(&self, aligned_addr: Address) -> Option<usize> {
+        let base = self.set_base(aligned_addr);
+        (base..(base + self.ways)).find(|&slot| self.address[slot] == aligned_addr)
     }
 
-    fn cached_read_single<F: AccessPhysicalMemory>(
+    pub fn cached_read_single<F: AccessPhysicalMemory>(
         &mut self,
         mem: &mut F,
         addr: PhysicalAddress,
         out: &mut [u8],
     ) -> Result<(), Error> {
+        self.trace(addr.address, out.len());
         if let Some(page) = addr.page {
             // try read from cache or fall back
             if self.is_cached_page_type(page.page_type) {
@@ -202,6 +424,99 @@ impl<T: CacheValidator> PageCache<T> {
         Ok(())
     }
 
+    /// Write `data` at physical `addr`, keeping the cache coherent.
+    ///
+    /// For cached page types the bytes are copied into the resident page buffer
+    /// and the slot is marked dirty. In write-through mode the write is also
+    /// forwarded to `mem` immediately; in write-back mode it is deferred until
+    /// eviction or [`flush`](Self::flush). Uncached page types bypass the cache
+    /// entirely.
+    pub fn cached_write<F: AccessPhysicalMemory>(
+        &mut self,
+        mem: &mut F,
+        addr: PhysicalAddress,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        if let Some(page) = addr.page {
+            if self.is_cached_page_type(page.page_type) {
+                let page_size = self.page_size();
+
+                // walk the write across page boundaries
+                let mut off = 0;
+                while off < data.len() {
+                    let paddr = addr.address + off;
+                    let aligned = paddr.as_page_aligned(page_size);
+                    let start = (paddr - aligned).as_usize();
+                    let len = (page_size.as_usize() - start).min(data.len() - off);
+                    let chunk = &data[off..(off + len)];
+
+                    // make sure the target page is resident before patching it
+                    let slot = match self.lookup(paddr) {
+                        Some(slot) => slot,
+                        None => {
+                            let slot = self.victim(paddr);
+                            let buf = self.page_from_index(slot);
+                            mem.phys_read_raw_into(
+                                PhysicalAddress {
+                                    address: aligned,
+                                    page: addr.page,
+                                },
+                                buf,
+                            )?;
+                            self.address[slot] = aligned;
+                            self.validator.validate_slot(slot);
+                            slot
+                        }
+                    };
+
+                    self.page_from_index(slot)[start..(start + len)].copy_from_slice(chunk);
+                    self.dirty[slot] = true;
+                    self.touch(slot);
+
+                    if !self.write_back {
+                        mem.phys_write_raw_into(paddr.into(), chunk)?;
+                        self.dirty[slot] = false;
+                    }
+
+                    off += len;
+                }
+                return Ok(());
+            }
+        }
+        mem.phys_write_raw_into(addr, data)
+    }
+
+    /// Flushes all buffered (dirty) pages to `mem` in a single coalesced write.
+    pub fn flush<F: AccessPhysicalMemory>(&mut self, mem: &mut F) -> Result<(), Error> {
+        use crate::mem::phys::PhysicalWriteData;
+
+        let mut writes: Vec<PhysicalWriteData> = Vec::new();
+
+        // pages evicted while no mem handle was available
+        for (addr, buf) in self.pending.iter() {
+            writes.push(PhysicalWriteData::new((*addr).into(), &buf[..]));
+        }
+
+        // currently-resident dirty pages
+        for slot in 0..self.address.len() {
+            if self.dirty[slot] {
+                let start = self.page_size.as_usize() * slot;
+                let buf = &self.cache[start..(start + self.page_size.as_usize())];
+                writes.push(PhysicalWriteData::new(self.address[slot].into(), buf));
+            }
+        }
+
+        if !writes.is_empty() {
+            mem.phys_write_raw_list(writes.as_slice())?;
+        }
+
+        self.pending.clear();
+        for d in self.dirty.iter_mut() {
+            *d = false;
+        }
+        Ok(())
+    }
+
     pub fn split_to_chunks<'a>(
         iter_elem: PhysicalReadType<'a>,
         page_size: Length,
@@ -244,6 +559,7 @@ impl<T: CacheValidator> PageCache<T> {
                 let mut ret = ArrayVec::<[_; 2]>::new();
                 ret.push(x);
                 if let ToDo((addr, out)) = &mut ret[0] {
+                    self.trace(addr.address, out.len());
                     if let Some(page) = addr.page {
                         if self.is_cached_page_type(page.page_type) {
                             let cached_page = self.cached_page_mut(addr.address);
@@ -266,8 +582,8 @@ impl<T: CacheValidator> PageCache<T> {
                                 }
                             } else {
                                 let aligned_addr = addr.address.as_page_aligned(page_size);
-                                let cached_page =
-                                    self.page_from_index(self.page_index(addr.address));
+                                let slot = self.slot_of(aligned_addr).unwrap();
+                                let cached_page = self.page_from_index(slot);
                                 let start = (addr.address - aligned_addr).as_usize();
                                 out.copy_from_slice(&cached_page[start..(start + out.len())]);
 
@@ -0,0 +1,229 @@
+use super::CacheValidator;
+use crate::architecture::Architecture;
+use crate::types::{Address, Length, PhysicalAddress};
+
+bitflags::bitflags! {
+    /// Permission and state bits of a cached virtual-to-physical mapping.
+    ///
+    /// These mirror the flags carried by the leaf page-table entry of the final
+    /// mapping (as decoded by the OS loaders) so that a cached translation keeps
+    /// the same access semantics the walk would have produced.
+    #[derive(Default)]
+    pub struct TranslationFlags: u8 {
+        /// The mapping is present and the cached physical page is usable.
+        const VALID = 0b0000_0001;
+        /// The mapping allows reads.
+        const READABLE = 0b0000_0010;
+        /// The mapping allows writes.
+        const WRITABLE = 0b0000_0100;
+        /// The mapping allows instruction fetches.
+        const EXECUTABLE = 0b0000_1000;
+        /// The mapping is accessible from user mode.
+        const USER = 0b0001_0000;
+        /// The accessed bit was set in the leaf entry.
+        const ACCESSED = 0b0010_0000;
+        /// The dirty bit was set in the leaf entry.
+        const DIRTY = 0b0100_0000;
+    }
+}
+
+/// A single resolved virtual-to-physical mapping.
+#[derive(Debug, Clone, Copy)]
+pub struct TranslationEntry {
+    /// Resolved physical address of the final mapping.
+    pub phys: PhysicalAddress,
+    /// Page size of the final mapping (to support large/huge pages).
+    pub page_size: Length,
+    /// Permission and state flags of the leaf entry.
+    pub flags: TranslationFlags,
+}
+
+/// Software TLB caching virtual-to-physical translations.
+///
+/// The `Windows<T>` introspection path walks the page tables on every virtual
+/// access; for repeated scans (`process_iter`, module enumeration) the same
+/// pages are translated over and over. `TranslationCache` keeps the resolved
+/// `PhysicalAddress`, the final mapping's page size and its permission flags
+/// indexed set-associatively by virtual page, exactly like the physical
+/// [`PageCache`](super::page_cache::PageCache). It is gated by the same
+/// [`CacheValidator`] so a CR3/DTB change drops every stale mapping without an
+/// explicit flush.
+#[derive(Clone)]
+pub struct TranslationCache<T: CacheValidator> {
+    // virtual page tag per slot (page-aligned, `Address::INVALID` when empty)
+    tag: Box<[Address]>,
+    entry: Box<[TranslationEntry]>,
+    // per-slot monotonic access counter used to pick the least-recently-used
+    // victim within a set
+    lru: Box<[u64]>,
+    clock: u64,
+    // associativity: number of slots per set (1 == direct mapped)
+    ways: usize,
+    num_sets: usize,
+    page_size: Length,
+    pub validator: T,
+}
+
+impl<T: CacheValidator> TranslationCache<T> {
+    /// Creates a direct-mapped translation cache (one way per set).
+    pub fn new(arch: Architecture, entries: usize, validator: T) -> Self {
+        Self::with_associativity(arch, entries, 1, validator)
+    }
+
+    /// Creates an `ways`-way set-associative translation cache holding up to
+    /// `entries` mappings.
+    ///
+    /// Higher associativity removes conflict misses when a process touches a few
+    /// widely-spaced regions (stack, heap, images) whose pages collide on the
+    /// same set, at the cost of a short linear scan bounded by `ways` per lookup.
+    pub fn with_associativity(
+        arch: Architecture,
+        entries: usize,
+        ways: usize,
+        mut validator: T,
+    ) -> Self {
+        let entries = entries.max(1);
+
+        // clamp associativity to the number of available slots and round the
+        // cache down to a whole number of sets
+        let ways = ways.max(1).min(entries);
+        let num_sets = (entries / ways).max(1);
+        let slots = num_sets * ways;
+
+        validator.allocate_slots(slots);
+
+        Self {
+            tag: vec![Address::INVALID; slots].into_boxed_slice(),
+            entry: vec![
+                TranslationEntry {
+                    phys: Address::INVALID.into(),
+                    page_size: arch.page_size(),
+                    flags: TranslationFlags::empty(),
+                };
+                slots
+            ]
+            .into_boxed_slice(),
+            lru: vec![0; slots].into_boxed_slice(),
+            clock: 0,
+            ways,
+            num_sets,
+            page_size: arch.page_size(),
+            validator,
+        }
+    }
+
+    /// Returns the first slot index of the set a virtual address maps to.
+    fn set_base(&self, addr: Address) -> usize {
+        let set = (addr.as_page_aligned(self.page_size).as_usize() / self.page_size.as_usize())
+            % self.num_sets;
+        set * self.ways
+    }
+
+    /// Marks a slot as most-recently-used.
+    fn touch(&mut self, slot: usize) {
+        self.clock += 1;
+        self.lru[slot] = self.clock;
+    }
+
+    /// Looks up the mapping for `addr`.
+    ///
+    /// On a hit the cached physical page and the in-page offset are combined so
+    /// the caller gets the resolved [`PhysicalAddress`] directly, skipping the
+    /// multi-level walk. A miss returns `None` and the caller is expected to run
+    /// the translator and [`insert`](Self::insert) the result.
+    pub fn try_translate(&mut self, addr: Address) -> Option<PhysicalAddress> {
+        let aligned = addr.as_page_aligned(self.page_size);
+        let base = self.set_base(addr);
+        for slot in base..(base + self.ways) {
+            if self.tag[slot] == aligned
+                && self.validator.is_slot_valid(slot)
+                && self.entry[slot].flags.contains(TranslationFlags::VALID)
+            {
+                self.touch(slot);
+                let offset = (addr - aligned).as_usize();
+                let cached = self.entry[slot].phys;
+                return Some(PhysicalAddress {
+                    address: cached.address + offset,
+                    page: cached.page,
+                });
+            }
+        }
+        None
+    }
+
+    /// Returns the full cached [`TranslationEntry`] for `addr`, if resident and
+    /// valid.
+    pub fn lookup_entry(&mut self, addr: Address) -> Option<TranslationEntry> {
+        let aligned = addr.as_page_aligned(self.page_size);
+        let base = self.set_base(addr);
+        for slot in base..(base + self.ways) {
+            if self.tag[slot] == aligned && self.validator.is_slot_valid(slot) {
+                self.touch(slot);
+                return Some(self.entry[slot]);
+            }
+        }
+        None
+    }
+
+    /// Chooses the slot to (re)use for `addr`: an existing slot already holding
+    /// the same virtual page, otherwise an invalid slot, otherwise the
+    /// least-recently-used slot in the set.
+    fn victim(&self, aligned: Address) -> usize {
+        let base = self.set_base(aligned);
+
+        for slot in base..(base + self.ways) {
+            if self.tag[slot] == aligned {
+                return slot;
+            }
+        }
+
+        let mut victim = base;
+        let mut victim_key = (true, u64::MAX);
+        for slot in base..(base + self.ways) {
+            let key = (self.validator.is_slot_valid(slot), self.lru[slot]);
+            if key < victim_key {
+                victim_key = key;
+                victim = slot;
+            }
+        }
+        victim
+    }
+
+    /// Inserts the result of a successful walk into the cache.
+    ///
+    /// `phys` is the resolved physical address of the *page base*, `page_size`
+    /// the size of the final mapping and `flags` its permission/state bits. The
+    /// slot is validated through the [`CacheValidator`] so it survives only until
+    /// the next translation-base change.
+    pub fn insert(
+        &mut self,
+        addr: Address,
+        phys: PhysicalAddress,
+        page_size: Length,
+        flags: TranslationFlags,
+    ) {
+        let aligned = addr.as_page_aligned(self.page_size);
+        let slot = self.victim(aligned);
+        self.tag[slot] = aligned;
+        self.entry[slot] = TranslationEntry {
+            phys,
+            page_size,
+            flags: flags | TranslationFlags::VALID,
+        };
+        self.validator.validate_slot(slot);
+        self.touch(slot);
+    }
+
+    /// Drops the mapping for `addr` if present.
+    pub fn invalidate(&mut self, addr: Address) {
+        let aligned = addr.as_page_aligned(self.page_size);
+        let base = self.set_base(addr);
+        for slot in base..(base + self.ways) {
+            if self.tag[slot] == aligned {
+                self.tag[slot] = Address::INVALID;
+                self.entry[slot].flags = TranslationFlags::empty();
+                self.validator.invalidate_slot(slot);
+            }
+        }
+    }
+}
@@ -3,6 +3,8 @@ use std::cell::RefCell;
 
 use flow_core::address::{Address};
 use flow_core::mem::{VirtualRead};
+use flow_core::mem::cache::{TimedCacheValidator, TranslationCache, TranslationFlags};
+use flow_core::types::PhysicalAddress;
 
 use crate::kernel::StartBlock;
 
@@ -12,6 +14,9 @@ pub mod module;
 
 use process::{ProcessIterator};
 
+/// Number of virtual-page translations the kernel-space software TLB retains.
+const TLB_ENTRIES: usize = 0x1000;
+
 // TODO: cache processes somewhat?
 #[derive(Clone)]
 pub struct Windows<T: VirtualRead> {
@@ -23,10 +28,57 @@ pub struct Windows<T: VirtualRead> {
 
     // TODO: refcell + shared access?
     pub kernel_pdb: Option<types::PDB>,
+
+    // software TLB shared across the repeated `process_iter`/module walks so the
+    // kernel page tables are not re-walked for every access to the same page
+    tlb: Rc<RefCell<TranslationCache<TimedCacheValidator>>>,
 }
 
 impl<T: VirtualRead> Windows<T> {
     pub fn process_iter(&mut self) -> ProcessIterator<'_, T> {
         ProcessIterator::new(self)
     }
+
+    /// Builds the kernel-space translation cache for a freshly constructed
+    /// `Windows`, keyed on the system architecture recorded in the start block.
+    pub fn new_tlb(start_block: &StartBlock) -> Rc<RefCell<TranslationCache<TimedCacheValidator>>> {
+        Rc::new(RefCell::new(TranslationCache::new(
+            start_block.arch,
+            TLB_ENTRIES,
+            TimedCacheValidator::default(),
+        )))
+    }
+
+    /// Translates a kernel virtual address through the software TLB, walking the
+    /// page tables only on a miss.
+    ///
+    /// The cache is validated by the same [`TimedCacheValidator`] as the physical
+    /// page cache, so a DTB change transparently drops every stale mapping.
+    pub fn cached_virt_to_phys(&mut self, addr: Address) -> Option<PhysicalAddress> {
+        // fast path: a resident, still-valid mapping skips the multi-level walk
+        if let Some(phys) = self.tlb.borrow_mut().try_translate(addr) {
+            return Some(phys);
+        }
+
+        // miss: walk the kernel page tables and remember the resolved page
+        let phys = self
+            .start_block
+            .arch
+            .virt_to_phys(&mut *self.mem.borrow_mut(), self.start_block.dtb, addr)
+            .ok()?;
+
+        // `insert` expects the page *base*: the in-page offset is re-applied by
+        // `try_translate` on a later hit, so caching the full address would add
+        // it twice.
+        let page_size = self.start_block.arch.page_size();
+        let page_base = PhysicalAddress {
+            address: phys.address.as_page_aligned(page_size),
+            page: phys.page,
+        };
+        self.tlb
+            .borrow_mut()
+            .insert(addr, page_base, page_size, TranslationFlags::READABLE);
+
+        Some(phys)
+    }
 }
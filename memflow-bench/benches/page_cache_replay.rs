@@ -0,0 +1,158 @@
+//! Replay harness for `PageCache`.
+//!
+//! Synthesizes traces of physical `(address, length)` read requests (the same
+//! shape `PageCache::take_recording` returns) and replays them against a `FileIoMemory`-backed snapshot through a
+//! `PageCache` at varying sizes and associativities, reporting the hit rate and
+//! elapsed time for random and sequential access patterns. This turns
+//! cache-sizing into a data-driven decision instead of a guess.
+
+extern crate memflow_bench;
+
+use criterion::*;
+
+use memflow::architecture::x86::x64;
+use memflow::connector::FileIoMemory;
+use memflow::mem::cache::page_cache::PageCache;
+use memflow::mem::cache::timed_validator::TimedCacheValidator;
+use memflow::mem::MemoryMap;
+use memflow::types::{size, Address, Length, PageType, PhysicalAddress};
+
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+const SNAPSHOT_SIZE: usize = size::mb(64);
+const PAGE_SIZE: usize = size::kb(4);
+const TRACE_LEN: usize = 64 * 1024;
+const READ_LEN: usize = 8;
+
+/// A seekable in-memory reader so the harness needs no temp file on disk.
+#[derive(Clone)]
+struct MemSnapshot(Cursor<Vec<u8>>);
+
+impl Read for MemSnapshot {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+impl Write for MemSnapshot {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+impl Seek for MemSnapshot {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+fn snapshot() -> FileIoMemory<MemSnapshot> {
+    let reader = MemSnapshot(Cursor::new(vec![0u8; SNAPSHOT_SIZE]));
+    let mut map = MemoryMap::new();
+    map.push_range(Address::null(), (SNAPSHOT_SIZE).into(), Address::null());
+    FileIoMemory::try_with_reader(reader, map).unwrap()
+}
+
+/// Deterministic xorshift so the benchmark is reproducible across runs.
+fn lcg(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// A sequential trace: one read per page walking the snapshot front to back,
+/// wrapping once it runs out of pages.
+fn sequential_trace() -> Vec<(Address, usize)> {
+    let pages = SNAPSHOT_SIZE / PAGE_SIZE;
+    (0..TRACE_LEN)
+        .map(|i| (Address::from(((i % pages) * PAGE_SIZE) as u64), READ_LEN))
+        .collect()
+}
+
+/// A random trace uniformly picking pages across the snapshot.
+fn random_trace() -> Vec<(Address, usize)> {
+    let pages = (SNAPSHOT_SIZE / PAGE_SIZE) as u64;
+    let mut state = 0x1234_5678_9abc_def0u64;
+    (0..TRACE_LEN)
+        .map(|_| {
+            let page = lcg(&mut state) % pages;
+            (Address::from(page * PAGE_SIZE as u64), READ_LEN)
+        })
+        .collect()
+}
+
+/// Replays a trace once through `cache`; its counters are read afterwards via
+/// `cache.stats()`.
+fn replay(
+    mem: &mut FileIoMemory<MemSnapshot>,
+    cache: &mut PageCache<TimedCacheValidator>,
+    trace: &[(Address, usize)],
+) {
+    let mut buf = vec![0u8; READ_LEN];
+    for &(addr, len) in trace {
+        // tag each address with a cached page type so the cache path is actually
+        // taken; a bare `addr.into()` carries `page: None` and reads straight
+        // through, exercising nothing
+        let paddr = PhysicalAddress::with_page(addr, PageType::READ_ONLY, Length::from(PAGE_SIZE));
+        let _ = cache.cached_read_single(mem, paddr, &mut buf[..len]);
+    }
+}
+
+fn page_cache_replay(c: &mut Criterion) {
+    let mut group = c.benchmark_group("page_cache_replay");
+
+    let sequential = sequential_trace();
+    let random = random_trace();
+
+    for &cache_mb in &[1usize, 4, 16] {
+        for &ways in &[1usize, 4] {
+            for (pattern, trace) in [("seq", &sequential), ("rand", &random)] {
+                // one untimed replay per configuration so its hit rate is
+                // printed next to criterion's elapsed-time report
+                {
+                    let mut mem = snapshot();
+                    let mut cache = PageCache::with_associativity(
+                        x64::ARCH,
+                        Length::from(size::mb(cache_mb)),
+                        PageType::PAGE_TABLE | PageType::READ_ONLY | PageType::WRITEABLE,
+                        ways,
+                        TimedCacheValidator::default(),
+                    );
+                    replay(&mut mem, &mut cache, trace);
+                    let rate = cache.stats().hit_rate();
+                    println!("{}-{}mb-{}way: hit rate {:.3}", pattern, cache_mb, ways, rate);
+                }
+                let id = BenchmarkId::new(format!("{}-{}mb-{}way", pattern, cache_mb, ways), TRACE_LEN);
+                group.bench_with_input(id, trace, |b, trace| {
+                    b.iter_batched(
+                        || {
+                            let mem = snapshot();
+                            let cache = PageCache::with_associativity(
+                                x64::ARCH,
+                                Length::from(size::mb(cache_mb)),
+                                PageType::PAGE_TABLE | PageType::READ_ONLY | PageType::WRITEABLE,
+                                ways,
+                                TimedCacheValidator::default(),
+                            );
+                            (mem, cache)
+                        },
+                        |(mut mem, mut cache)| {
+                            replay(&mut mem, &mut cache, trace);
+                            // keep the replay and its hit-rate computation from
+                            // being optimised away in the timed runs
+                            black_box(cache.stats().hit_rate())
+                        },
+                        BatchSize::SmallInput,
+                    );
+                });
+            }
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, page_cache_replay);
+criterion_main!(benches);
@@ -0,0 +1,161 @@
+/*!
+PDB-free offset auto-discovery.
+
+[`Win32OffsetBuilder::build`](super::Win32OffsetBuilder::build) fails outright when
+neither the symbol store nor the bundled static list knows the exact GUID/build of
+the target kernel — a common situation on Insider or hot-patched builds. This module
+derives the handful of `_EPROCESS`/`_KPROCESS` offsets needed to bootstrap a walk at
+runtime, anchoring on the always-present System process (PID 4) instead of a PDB.
+
+The discovery is comparatively expensive (several windowed scans), so the derived
+[`Win32OffsetsData`] should be cached by the caller for the lifetime of a target.
+*/
+
+use std::prelude::v1::*;
+
+use super::Win32OffsetsData;
+use crate::error::{Error, Result};
+
+use memflow::mem::VirtualMemory;
+use memflow::types::Address;
+
+/// Window around an `_EPROCESS` that is scanned for anchor fields.
+const SCAN_WINDOW: usize = 0x600;
+/// Number of ring hops verified when locating `ActiveProcessLinks`.
+const RING_HOPS: usize = 4;
+
+/// Derive the core `_EPROCESS`/`_KPROCESS` offsets from the System process.
+///
+/// `system_eprocess` is the virtual address of the System `_EPROCESS` (resolved
+/// by the caller from `PsInitialSystemProcess`, or by scanning for the
+/// `"System"` image name), and `dtb` is the System directory table base used to
+/// translate it. Fields that cannot be derived heuristically are left zeroed;
+/// callers that need them must fall back to a PDB.
+pub fn discover<T: VirtualMemory>(
+    mem: &mut T,
+    system_eprocess: Address,
+    dtb: Address,
+) -> Result<Win32OffsetsData> {
+    let mut window = vec![0u8; SCAN_WINDOW];
+    mem.virt_read_raw_into(system_eprocess, &mut window)
+        .map_err(|_| Error::Other("unable to read System _EPROCESS window"))?;
+
+    let eproc_name = find_image_name(&window)
+        .ok_or(Error::Other("could not locate ImageFileName anchor"))?;
+    let eproc_pid =
+        find_pid(&window).ok_or(Error::Other("could not locate UniqueProcessId anchor"))?;
+    let eproc_link = find_active_links(mem, system_eprocess, &window, eproc_name, eproc_pid)
+        .ok_or(Error::Other("could not locate ActiveProcessLinks anchor"))?;
+    let kproc_dtb = find_dtb(&window, dtb)
+        .ok_or(Error::Other("could not locate DirectoryTableBase anchor"))?;
+
+    Ok(Win32OffsetsData {
+        list_blink: 0x8,
+        eproc_link,
+        kproc_dtb,
+        eproc_pid,
+        eproc_name,
+        eproc_peb: 0,
+        eproc_thread_list: 0,
+        eproc_wow64: 0,
+        kthread_teb: 0,
+        ethread_list_entry: 0,
+        teb_peb: 0,
+        teb_peb_x86: 0,
+    })
+}
+
+/// `ImageFileName` holds the NUL-terminated `"System"` string.
+fn find_image_name(window: &[u8]) -> Option<usize> {
+    window
+        .windows(b"System\0".len())
+        .position(|w| w == b"System\0")
+}
+
+/// `UniqueProcessId` of the System process is always 4.
+fn find_pid(window: &[u8]) -> Option<usize> {
+    window
+        .chunks_exact(8)
+        .position(|c| u64::from_le_bytes(c.try_into().unwrap()) == 4)
+        .map(|idx| idx * 8)
+}
+
+/// `DirectoryTableBase` of the System `_KPROCESS` equals the known DTB.
+fn find_dtb(window: &[u8], dtb: Address) -> Option<usize> {
+    let needle = dtb.as_u64();
+    window
+        .chunks_exact(8)
+        .position(|c| u64::from_le_bytes(c.try_into().unwrap()) == needle)
+        .map(|idx| idx * 8)
+}
+
+/// Locate `ActiveProcessLinks` by treating each pointer-aligned offset as a
+/// `_LIST_ENTRY` and following `Flink` for `RING_HOPS` hops. The offset is
+/// accepted only if every neighbour resolves (via `entry - offset`) to a struct
+/// whose `ImageFileName`/`UniqueProcessId` anchors are themselves valid. Only
+/// those hops are checked; the walk does not verify that the ring closes back
+/// on the System process.
+fn find_active_links<T: VirtualMemory>(
+    mem: &mut T,
+    system_eprocess: Address,
+    window: &[u8],
+    eproc_name: usize,
+    eproc_pid: usize,
+) -> Option<usize> {
+    for off in (0..=window.len().saturating_sub(8)).step_by(8) {
+        let flink = u64::from_le_bytes(window[off..off + 8].try_into().ok()?);
+        if flink == 0 || flink & 0x7 != 0 {
+            continue;
+        }
+
+        let mut entry = Address::from(flink);
+        let mut ok = true;
+        for _ in 0..RING_HOPS {
+            let candidate = entry.as_u64().checked_sub(off as u64)?;
+            if !neighbour_is_valid(mem, Address::from(candidate), eproc_name, eproc_pid) {
+                ok = false;
+                break;
+            }
+            let mut next = [0u8; 8];
+            if mem.virt_read_raw_into(entry, &mut next).is_err() {
+                ok = false;
+                break;
+            }
+            entry = Address::from(u64::from_le_bytes(next));
+        }
+
+        // the entry reached after the last hop must be the System process itself
+        // or another valid `_EPROCESS`
+        if ok {
+            let back = entry.as_u64().checked_sub(off as u64)?;
+            if Address::from(back) == system_eprocess
+                || neighbour_is_valid(mem, Address::from(back), eproc_name, eproc_pid)
+            {
+                return Some(off);
+            }
+        }
+    }
+    None
+}
+
+fn neighbour_is_valid<T: VirtualMemory>(
+    mem: &mut T,
+    eprocess: Address,
+    eproc_name: usize,
+    eproc_pid: usize,
+) -> bool {
+    let mut name = [0u8; 15];
+    if mem.virt_read_raw_into(eprocess + eproc_name, &mut name).is_err() {
+        return false;
+    }
+    if !name.iter().take_while(|&&b| b != 0).all(u8::is_ascii_graphic) {
+        return false;
+    }
+
+    let mut pid = [0u8; 8];
+    if mem.virt_read_raw_into(eprocess + eproc_pid, &mut pid).is_err() {
+        return false;
+    }
+    let pid = u64::from_le_bytes(pid);
+    pid % 4 == 0 && pid < 0x40_0000
+}
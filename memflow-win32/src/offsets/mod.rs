@@ -7,6 +7,26 @@ pub mod offset_data;
 #[doc(hidden)]
 pub use offset_data::{Win32OffsetsData, Win32OffsetsFile};
 
+#[cfg(feature = "symstore")]
+pub mod pool_scan;
+#[cfg(feature = "symstore")]
+pub use pool_scan::{PoolProcess, PoolScanner, Win32PoolOffsets};
+
+#[cfg(feature = "symstore")]
+pub mod symbols;
+#[cfg(feature = "symstore")]
+pub use symbols::{PdbSymbols, Win32Symbols};
+
+#[cfg(feature = "symstore")]
+pub mod stack_walk;
+#[cfg(feature = "symstore")]
+pub use stack_walk::{RuntimeFunction, StackFrame, StackWalker, Win32StackOffsets};
+
+pub mod heuristics;
+
+pub mod profile;
+pub use profile::Win32OffsetProfile;
+
 #[cfg(feature = "symstore")]
 pub use {pdb_struct::PdbStruct, symstore::*};
 
@@ -167,6 +187,16 @@ impl Win32Offsets {
     pub fn builder() -> Win32OffsetBuilder {
         Win32OffsetBuilder::default()
     }
+
+    /// Resolve the RVAs of a caller-supplied set of kernel globals from a PDB.
+    ///
+    /// This complements [`from_pdb_slice`](Self::from_pdb_slice), which only
+    /// harvests struct field offsets, by additionally exposing exported globals
+    /// (e.g. `PsActiveProcessHead`, `PsLoadedModuleList`).
+    #[cfg(feature = "symstore")]
+    pub fn symbols_from_pdb_slice(pdb_slice: &[u8], names: &[&str]) -> Result<Win32Symbols> {
+        Ok(PdbSymbols::with(pdb_slice)?.resolve(names))
+    }
 }
 
 pub struct Win32OffsetBuilder {
@@ -175,6 +205,14 @@ pub struct Win32OffsetBuilder {
 
     guid: Option<Win32GUID>,
     winver: Option<Win32Version>,
+
+    // offsets derived at runtime (e.g. via `build_with_heuristics`) are cached
+    // here so the expensive scan only has to run once per builder.
+    offsets: Option<Win32OffsetsData>,
+
+    // user-writable directory checked before the network symbol store and where
+    // freshly-derived offsets are written back.
+    offset_cache_dir: Option<std::path::PathBuf>,
 }
 
 impl Default for Win32OffsetBuilder {
@@ -185,6 +223,9 @@ impl Default for Win32OffsetBuilder {
 
             guid: None,
             winver: None,
+
+            offsets: None,
+            offset_cache_dir: None,
         }
     }
 }
@@ -195,13 +236,25 @@ impl Win32OffsetBuilder {
     }
 
     pub fn build(self) -> Result<Win32Offsets> {
+        // use runtime-derived offsets if they were cached via build_with_heuristics
+        if let Some(offsets) = &self.offsets {
+            return Ok(Win32Offsets(offsets.clone()));
+        }
+
         if self.guid.is_none() && self.winver.is_none() {
             return Err(Error::Other(
                 "building win32 offsets requires either a guid or winver",
             ));
         }
+        // check the user-writable offset cache before hitting the network
+        if let Ok(offs) = self.build_with_offset_cache() {
+            return Ok(offs);
+        }
+
         // try to build via symbol store
         if let Ok(offs) = self.build_with_symbol_store() {
+            // persist freshly-derived offsets back to the cache for next time
+            self.write_offset_cache(&offs);
             return Ok(offs);
         }
 
@@ -213,6 +266,66 @@ impl Win32OffsetBuilder {
         Err(Error::Other("not found"))
     }
 
+    /// Sets a user-writable directory that is consulted for a cached offset
+    /// profile before the network symbol store, and where freshly-derived
+    /// offsets are written back.
+    pub fn offset_cache_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.offset_cache_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    fn build_with_offset_cache(&self) -> Result<Win32Offsets> {
+        let dir = self
+            .offset_cache_dir
+            .as_ref()
+            .ok_or(Error::Other("no offset cache directory configured"))?;
+        let guid = self
+            .guid
+            .as_ref()
+            .ok_or(Error::Other("offset cache lookup requires a guid"))?;
+        #[cfg(feature = "serde")]
+        {
+            Win32Offsets::load_from_file(profile::cache_path(dir, guid))
+        }
+        #[cfg(not(feature = "serde"))]
+        {
+            let _ = (dir, guid);
+            Err(Error::Other("offset cache requires the serde feature"))
+        }
+    }
+
+    fn write_offset_cache(&self, offsets: &Win32Offsets) {
+        #[cfg(feature = "serde")]
+        if let (Some(dir), Some(guid), Some(winver)) =
+            (&self.offset_cache_dir, &self.guid, &self.winver)
+        {
+            let _ = std::fs::create_dir_all(dir);
+            if let Err(err) = offsets.save_to_file(profile::cache_path(dir, guid), guid, winver) {
+                log::warn!("unable to write offset cache: {:?}", err);
+            }
+        }
+        #[cfg(not(feature = "serde"))]
+        let _ = offsets;
+    }
+
+    /// Derive the core offsets heuristically from the running kernel instead of
+    /// a PDB, anchoring on the System process (PID 4).
+    ///
+    /// This is a fallback for kernels that are present in neither the symbol
+    /// store nor the bundled offset list. The derived [`Win32OffsetsData`] is
+    /// cached on the builder so a subsequent [`build`](Self::build) returns it
+    /// without re-scanning.
+    pub fn build_with_heuristics<T: memflow::mem::VirtualMemory>(
+        &mut self,
+        mem: &mut T,
+        system_eprocess: memflow::types::Address,
+        dtb_of_system: memflow::types::Address,
+    ) -> Result<Win32Offsets> {
+        let offsets = heuristics::discover(mem, system_eprocess, dtb_of_system)?;
+        self.offsets = Some(offsets.clone());
+        Ok(Win32Offsets(offsets))
+    }
+
     fn build_with_offset_list(&self) -> Result<Win32Offsets> {
         let bytes = &include_bytes!(concat!(env!("OUT_DIR"), "/win32_offsets.bin"))[..];
 
@@ -0,0 +1,313 @@
+/*!
+Pool-tag scanning for recovering `_EPROCESS` objects that have been unlinked
+from `_EPROCESS::ActiveProcessLinks` (classic DKOM process hiding).
+
+The linked-list walk driven by the offsets from [`Win32Offsets::from_pdb_slice`](super::Win32Offsets::from_pdb_slice)
+only ever sees processes that are still threaded onto the active-process list.
+Rootkits hide processes by unlinking them from exactly that list, and terminated
+processes linger in the non-paged pool until their last reference is dropped.
+
+This module scans the non-paged pool directly for the `"Proc"` allocation tag,
+walks back to the enclosing `_POOL_HEADER`, skips the optional object-header
+prefixes described by `_OBJECT_HEADER::InfoMask`, and validates the resulting
+candidate `_EPROCESS`. The survivors can then be diffed against the list walk to
+flag processes that are present in memory but missing from the active list.
+*/
+
+use std::prelude::v1::*;
+
+use super::{pdb_struct::PdbStruct, Win32Offsets};
+use crate::error::{Error, Result};
+
+use memflow::mem::VirtualMemory;
+use memflow::types::Address;
+
+/// The `"Proc"` pool tag stamped on `_EPROCESS` allocations.
+pub const POOL_TAG_PROCESS: [u8; 4] = *b"Proc";
+/// High bit set on the tag of protected pool allocations.
+const POOL_TAG_PROTECTED_BIT: u8 = 0x80;
+
+/// Offsets of the structures required to walk the non-paged pool by hand.
+///
+/// These complement [`Win32OffsetsData`](super::Win32OffsetsData), which only
+/// carries what is needed for the list walk. They are harvested from the same
+/// kernel PDB via [`Win32PoolOffsets::from_pdb_slice`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Win32PoolOffsets {
+    /// Size of `_POOL_HEADER` (0x10 on x64).
+    pub pool_header_size: usize,
+    /// Offset of `_POOL_HEADER::PoolTag` (0x4 on x64).
+    pub pool_tag: usize,
+    /// Size of `_OBJECT_HEADER` up to (but excluding) its `Body`.
+    pub obj_header_size: usize,
+    /// Offset of `_OBJECT_HEADER::InfoMask`.
+    pub obj_header_info_mask: usize,
+    /// Offset of `_OBJECT_HEADER::Body` (the start of the contained object).
+    pub obj_header_body: usize,
+    /// Offset of `_EPROCESS::CreateTime`.
+    pub eproc_create_time: usize,
+}
+
+impl Win32PoolOffsets {
+    /// Harvest the pool/object-header offsets from a kernel PDB.
+    pub fn from_pdb_slice(pdb_slice: &[u8]) -> Result<Self> {
+        let pool = PdbStruct::with(pdb_slice, "_POOL_HEADER")
+            .map_err(|_| Error::PDB("_POOL_HEADER not found"))?;
+        let obj = PdbStruct::with(pdb_slice, "_OBJECT_HEADER")
+            .map_err(|_| Error::PDB("_OBJECT_HEADER not found"))?;
+        let eproc = PdbStruct::with(pdb_slice, "_EPROCESS")
+            .map_err(|_| Error::PDB("_EPROCESS not found"))?;
+
+        let pool_tag = pool
+            .find_field("PoolTag")
+            .ok_or_else(|| Error::PDB("_POOL_HEADER::PoolTag not found"))?
+            .offset as _;
+
+        let obj_header_info_mask = obj
+            .find_field("InfoMask")
+            .ok_or_else(|| Error::PDB("_OBJECT_HEADER::InfoMask not found"))?
+            .offset as _;
+        let obj_header_body = obj
+            .find_field("Body")
+            .ok_or_else(|| Error::PDB("_OBJECT_HEADER::Body not found"))?
+            .offset as _;
+
+        let eproc_create_time = eproc
+            .find_field("CreateTime")
+            .ok_or_else(|| Error::PDB("_EPROCESS::CreateTime not found"))?
+            .offset as _;
+
+        Ok(Self {
+            // `_POOL_HEADER` is a fixed 0x10 bytes on x64; the PDB reports it as
+            // the offset of the first byte past the header (its `Body`-equivalent
+            // is the following allocation), so we fall back to the known size.
+            pool_header_size: 0x10,
+            pool_tag,
+            obj_header_size: obj_header_body,
+            obj_header_info_mask,
+            obj_header_body,
+            eproc_create_time,
+        })
+    }
+}
+
+/// Each set bit in `_OBJECT_HEADER::InfoMask` prepends a fixed-size optional
+/// header in front of the object header. The table is ordered from the bit
+/// closest to the object header outwards, matching the kernel's
+/// `ObpInfoMaskToOffset` logic.
+const OPTIONAL_HEADER_SIZES: [(u8, usize); 8] = [
+    (0x01, 0x20), // _OBJECT_HEADER_CREATOR_INFO
+    (0x02, 0x20), // _OBJECT_HEADER_NAME_INFO
+    (0x04, 0x10), // _OBJECT_HEADER_HANDLE_INFO
+    (0x08, 0x20), // _OBJECT_HEADER_QUOTA_INFO
+    (0x10, 0x10), // _OBJECT_HEADER_PROCESS_INFO
+    (0x20, 0x18), // _OBJECT_HEADER_AUDIT_INFO
+    (0x40, 0x10), // _OBJECT_HEADER_HANDLE_REVOCATION_INFO
+    (0x80, 0x10), // _OBJECT_HEADER_EXTENDED_INFO
+];
+
+/// Total size of the optional headers that precede an object header with the
+/// given `InfoMask`.
+fn optional_header_prefix(info_mask: u8) -> usize {
+    OPTIONAL_HEADER_SIZES
+        .iter()
+        .filter(|(bit, _)| info_mask & bit != 0)
+        .map(|(_, size)| size)
+        .sum()
+}
+
+/// A process recovered from the non-paged pool.
+#[derive(Debug, Clone)]
+pub struct PoolProcess {
+    /// Virtual address of the candidate `_EPROCESS`.
+    pub eprocess: Address,
+    /// `_EPROCESS::UniqueProcessId`.
+    pub pid: u64,
+    /// `_EPROCESS::ImageFileName`, trimmed of trailing NULs.
+    pub name: String,
+}
+
+/// Scans the non-paged pool for hidden/terminated `_EPROCESS` objects.
+///
+/// `mem` is read in page-sized chunks over `[pool_start, pool_end)`. The caller
+/// supplies the pool range (e.g. from `MmNonPagedPoolStart`/`MmNonPagedPoolEnd`)
+/// and the matching [`Win32Offsets`] so that recovered candidates can be decoded.
+pub struct PoolScanner<'a> {
+    offsets: &'a Win32Offsets,
+    pool: &'a Win32PoolOffsets,
+    include_protected: bool,
+}
+
+impl<'a> PoolScanner<'a> {
+    pub fn new(offsets: &'a Win32Offsets, pool: &'a Win32PoolOffsets) -> Self {
+        Self {
+            offsets,
+            pool,
+            include_protected: true,
+        }
+    }
+
+    /// Whether to also match the protected-allocation variant of the tag (high
+    /// bit set). Defaults to `true`.
+    pub fn include_protected(mut self, include: bool) -> Self {
+        self.include_protected = include;
+        self
+    }
+
+    /// Scan `[pool_start, pool_end)` and return every candidate that survives
+    /// validation.
+    pub fn scan<T: VirtualMemory>(
+        &self,
+        mem: &mut T,
+        pool_start: Address,
+        pool_end: Address,
+    ) -> Result<Vec<PoolProcess>> {
+        const PAGE_SIZE: usize = 0x1000;
+
+        let mut out = Vec::new();
+        let mut page = vec![0u8; PAGE_SIZE];
+
+        let mut addr = pool_start.as_u64();
+        while addr < pool_end.as_u64() {
+            if mem
+                .virt_read_raw_into(Address::from(addr), &mut page)
+                .is_err()
+            {
+                // unmapped hole in the pool range, skip a page
+                addr += PAGE_SIZE as u64;
+                continue;
+            }
+
+            for off in 0..PAGE_SIZE.saturating_sub(POOL_TAG_PROCESS.len()) {
+                if self.tag_matches(&page[off..off + POOL_TAG_PROCESS.len()]) {
+                    let tag_addr = Address::from(addr + off as u64);
+                    if let Some(proc) = self.decode_candidate(mem, tag_addr) {
+                        out.push(proc);
+                    }
+                }
+            }
+
+            addr += PAGE_SIZE as u64;
+        }
+
+        Ok(out)
+    }
+
+    fn tag_matches(&self, bytes: &[u8]) -> bool {
+        if bytes == POOL_TAG_PROCESS {
+            return true;
+        }
+        // the last character may have the protected-allocation high bit set
+        self.include_protected
+            && bytes[..3] == POOL_TAG_PROCESS[..3]
+            && bytes[3] == POOL_TAG_PROCESS[3] | POOL_TAG_PROTECTED_BIT
+    }
+
+    /// Given the virtual address of a matched `PoolTag`, back up to the pool
+    /// header, skip the optional object headers and validate the `_EPROCESS`.
+    fn decode_candidate<T: VirtualMemory>(
+        &self,
+        mem: &mut T,
+        tag_addr: Address,
+    ) -> Option<PoolProcess> {
+        let header_base = tag_addr.as_u64().checked_sub(self.pool.pool_tag as u64)?;
+        let optional_base = header_base + self.pool.pool_header_size as u64;
+
+        // The optional object headers sit between the `_POOL_HEADER` and the
+        // `_OBJECT_HEADER`, and their total size is described by bits in
+        // `_OBJECT_HEADER::InfoMask` — which itself lives inside the object
+        // header, past the optional headers. Resolve the self-referential layout
+        // by iterating to a fixed point: start by assuming no optional headers,
+        // read the tentative InfoMask, recompute the prefix and relocate the
+        // object header until the prefix stops changing. The true prefix is a
+        // fixed point, and an allocation can carry at most all eight headers so
+        // the loop is bounded.
+        let mut prefix = 0u64;
+        for _ in 0..=OPTIONAL_HEADER_SIZES.len() {
+            let obj_header = optional_base + prefix;
+
+            let mut info_mask = [0u8; 1];
+            mem.virt_read_raw_into(
+                Address::from(obj_header + self.pool.obj_header_info_mask as u64),
+                &mut info_mask,
+            )
+            .ok()?;
+
+            let next = optional_header_prefix(info_mask[0]) as u64;
+            if next == prefix {
+                let eprocess = Address::from(obj_header + self.pool.obj_header_body as u64);
+                return self.validate(mem, eprocess);
+            }
+            prefix = next;
+        }
+
+        None
+    }
+
+    /// Validate a candidate `_EPROCESS` by sanity-checking PID, image name and
+    /// creation time.
+    fn validate<T: VirtualMemory>(
+        &self,
+        mem: &mut T,
+        eprocess: Address,
+    ) -> Option<PoolProcess> {
+        let data = &self.offsets.0;
+
+        let mut pid_buf = [0u8; 8];
+        mem.virt_read_raw_into(eprocess + data.eproc_pid, &mut pid_buf)
+            .ok()?;
+        let pid = u64::from_le_bytes(pid_buf);
+        // PIDs are small multiples of 4 handed out from the PID bitmap
+        if pid == 0 || pid % 4 != 0 || pid > 0x40_0000 {
+            return None;
+        }
+
+        let mut name_buf = [0u8; 16];
+        mem.virt_read_raw_into(eprocess + data.eproc_name, &mut name_buf)
+            .ok()?;
+        if !name_buf.iter().take_while(|&&b| b != 0).all(u8::is_ascii_graphic) {
+            return None;
+        }
+        let name = String::from_utf8_lossy(
+            &name_buf[..name_buf.iter().position(|&b| b == 0).unwrap_or(name_buf.len())],
+        )
+        .into_owned();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut create_buf = [0u8; 8];
+        mem.virt_read_raw_into(eprocess + self.pool.eproc_create_time, &mut create_buf)
+            .ok()?;
+        let create_time = u64::from_le_bytes(create_buf);
+        // FILETIME between 2000-01-01 and 2100-01-01 (100ns ticks since 1601)
+        const FILETIME_2000: u64 = 125_911_584_000_000_000;
+        const FILETIME_2100: u64 = 157_469_184_000_000_000;
+        if !(FILETIME_2000..FILETIME_2100).contains(&create_time) {
+            return None;
+        }
+
+        Some(PoolProcess {
+            eprocess,
+            pid,
+            name,
+        })
+    }
+}
+
+impl Win32Offsets {
+    /// Diff the set of pool-scanned processes against a set of addresses known
+    /// from the `ActiveProcessLinks` walk, returning the entries that are
+    /// present in the pool but missing from the linked list (i.e. hidden).
+    pub fn hidden_processes(
+        scanned: &[PoolProcess],
+        linked: &[Address],
+    ) -> Vec<PoolProcess> {
+        scanned
+            .iter()
+            .filter(|p| !linked.contains(&p.eprocess))
+            .cloned()
+            .collect()
+    }
+}
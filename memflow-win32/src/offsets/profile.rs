@@ -0,0 +1,119 @@
+/*!
+Persist and reload user-derived offsets as an editable on-disk profile.
+
+A PDB- or heuristically-derived [`Win32OffsetsData`] is otherwise thrown away
+after each run, forcing offline/air-gapped users to re-acquire a PDB every launch.
+This module serializes offsets to a JSON profile keyed by PDB GUID and NT
+major/minor/build, so they can be shipped and hand-edited for kernels that are not
+in the bundled list, mirroring how other tools keep a local offset cache.
+*/
+
+use std::prelude::v1::*;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Win32Offsets, Win32OffsetsData};
+use crate::error::{Error, Result};
+use crate::kernel::{Win32GUID, Win32Version};
+
+/// A single on-disk offset profile.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Win32OffsetProfile {
+    pub pdb_file_name: String,
+    pub pdb_guid: String,
+    pub nt_major_version: u32,
+    pub nt_minor_version: u32,
+    pub nt_build_number: u32,
+
+    pub list_blink: usize,
+    pub eproc_link: usize,
+    pub kproc_dtb: usize,
+    pub eproc_pid: usize,
+    pub eproc_name: usize,
+    pub eproc_peb: usize,
+    pub eproc_thread_list: usize,
+    pub eproc_wow64: usize,
+    pub kthread_teb: usize,
+    pub ethread_list_entry: usize,
+    pub teb_peb: usize,
+    pub teb_peb_x86: usize,
+}
+
+impl Win32OffsetProfile {
+    fn from_parts(guid: &Win32GUID, winver: &Win32Version, data: &Win32OffsetsData) -> Self {
+        Self {
+            pdb_file_name: guid.file_name.clone(),
+            pdb_guid: guid.guid.clone(),
+            nt_major_version: winver.major_version() as u32,
+            nt_minor_version: winver.minor_version() as u32,
+            nt_build_number: winver.build_number() as u32,
+
+            list_blink: data.list_blink as usize,
+            eproc_link: data.eproc_link as usize,
+            kproc_dtb: data.kproc_dtb as usize,
+            eproc_pid: data.eproc_pid as usize,
+            eproc_name: data.eproc_name as usize,
+            eproc_peb: data.eproc_peb as usize,
+            eproc_thread_list: data.eproc_thread_list as usize,
+            eproc_wow64: data.eproc_wow64 as usize,
+            kthread_teb: data.kthread_teb as usize,
+            ethread_list_entry: data.ethread_list_entry as usize,
+            teb_peb: data.teb_peb as usize,
+            teb_peb_x86: data.teb_peb_x86 as usize,
+        }
+    }
+
+    fn into_data(self) -> Win32OffsetsData {
+        Win32OffsetsData {
+            list_blink: self.list_blink as _,
+            eproc_link: self.eproc_link as _,
+            kproc_dtb: self.kproc_dtb as _,
+            eproc_pid: self.eproc_pid as _,
+            eproc_name: self.eproc_name as _,
+            eproc_peb: self.eproc_peb as _,
+            eproc_thread_list: self.eproc_thread_list as _,
+            eproc_wow64: self.eproc_wow64 as _,
+            kthread_teb: self.kthread_teb as _,
+            ethread_list_entry: self.ethread_list_entry as _,
+            teb_peb: self.teb_peb as _,
+            teb_peb_x86: self.teb_peb_x86 as _,
+        }
+    }
+}
+
+/// File name a profile is cached under inside an `offset_cache_dir`.
+pub(crate) fn cache_file_name(guid: &Win32GUID) -> String {
+    format!("{}-{}.json", guid.file_name, guid.guid)
+}
+
+#[cfg(feature = "serde")]
+impl Win32Offsets {
+    /// Serialize the offsets as a JSON profile keyed by the given GUID/version.
+    pub fn save_to_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        guid: &Win32GUID,
+        winver: &Win32Version,
+    ) -> Result<()> {
+        let profile = Win32OffsetProfile::from_parts(guid, winver, &self.0);
+        let json = serde_json::to_string_pretty(&profile)
+            .map_err(|_| Error::Other("unable to serialize offset profile"))?;
+        fs::write(path, json).map_err(|_| Error::Other("unable to write offset profile"))
+    }
+
+    /// Load offsets from a previously saved JSON profile.
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let json =
+            fs::read_to_string(path).map_err(|_| Error::Other("unable to read offset profile"))?;
+        let profile: Win32OffsetProfile = serde_json::from_str(&json)
+            .map_err(|_| Error::Other("unable to parse offset profile"))?;
+        Ok(Win32Offsets(profile.into_data()))
+    }
+}
+
+/// Resolve the path a profile for `guid` would live at inside `dir`.
+pub(crate) fn cache_path(dir: &Path, guid: &Win32GUID) -> PathBuf {
+    dir.join(cache_file_name(guid))
+}
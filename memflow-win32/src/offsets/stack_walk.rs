@@ -0,0 +1,250 @@
+/*!
+Kernel stack unwinding backed by PDB-derived unwind data.
+
+Raw thread enumeration only yields a saved `RIP`/`RSP`. This module turns that
+into an actionable backtrace by stepping frames and mapping each return address
+back through the [symbol table](super::symbols) into a readable
+`symbol+0x..` frame (the module name is not part of the rendered frame).
+
+For x64 this is driven by the image's `.pdata` / `RUNTIME_FUNCTION` unwind tables:
+given the covering function's `UNWIND_INFO`, we replay its prologue unwind codes
+to compute how much the prologue adjusted `RSP`, pop the saved return address and
+continue at the caller. Only x64 frames are unwound: there is no x86 path here,
+neither via the PDB FPO/frame-data stream nor via a saved-frame-pointer walk.
+
+The `_ETHREAD`/`_KTHREAD` stack-base and kernel-stack-pointer offsets needed to
+seed the unwinder are harvested by [`Win32StackOffsets::from_pdb_slice`].
+*/
+
+use std::prelude::v1::*;
+
+use super::pdb_struct::PdbStruct;
+use super::symbols::Win32Symbols;
+use crate::error::{Error, Result};
+
+use memflow::mem::VirtualMemory;
+use memflow::types::Address;
+
+/// Offsets required to read a thread's saved kernel context.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Win32StackOffsets {
+    /// `_KTHREAD::StackBase`.
+    pub kthread_stack_base: usize,
+    /// `_KTHREAD::StackLimit`.
+    pub kthread_stack_limit: usize,
+    /// `_KTHREAD::KernelStack` (saved kernel `RSP`).
+    pub kthread_kernel_stack: usize,
+}
+
+impl Win32StackOffsets {
+    /// Harvest the thread stack offsets from a kernel PDB.
+    pub fn from_pdb_slice(pdb_slice: &[u8]) -> Result<Self> {
+        let kthread = PdbStruct::with(pdb_slice, "_KTHREAD")
+            .map_err(|_| Error::PDB("_KTHREAD not found"))?;
+
+        let kthread_stack_base = kthread
+            .find_field("StackBase")
+            .ok_or_else(|| Error::PDB("_KTHREAD::StackBase not found"))?
+            .offset as _;
+        let kthread_stack_limit = kthread
+            .find_field("StackLimit")
+            .ok_or_else(|| Error::PDB("_KTHREAD::StackLimit not found"))?
+            .offset as _;
+        let kthread_kernel_stack = kthread
+            .find_field("KernelStack")
+            .ok_or_else(|| Error::PDB("_KTHREAD::KernelStack not found"))?
+            .offset as _;
+
+        Ok(Self {
+            kthread_stack_base,
+            kthread_stack_limit,
+            kthread_kernel_stack,
+        })
+    }
+}
+
+/// A single resolved stack frame.
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    /// The return address on the stack.
+    pub ret_addr: Address,
+    /// Human readable `symbol+0x..` (without a module prefix), if it could be
+    /// resolved.
+    pub symbol: Option<String>,
+}
+
+/// An entry of the `.pdata` `RUNTIME_FUNCTION` table (x64).
+#[derive(Debug, Clone, Copy)]
+pub struct RuntimeFunction {
+    /// RVA of the first instruction of the function.
+    pub begin: u32,
+    /// RVA of the byte past the last instruction.
+    pub end: u32,
+    /// RVA of the `UNWIND_INFO` for the function.
+    pub unwind_info: u32,
+}
+
+impl RuntimeFunction {
+    fn covers(&self, rva: u32) -> bool {
+        rva >= self.begin && rva < self.end
+    }
+}
+
+// x64 UNWIND_CODE operation codes (see the AMD64 exception-handling ABI).
+const UWOP_PUSH_NONVOL: u8 = 0;
+const UWOP_ALLOC_LARGE: u8 = 1;
+const UWOP_ALLOC_SMALL: u8 = 2;
+const UWOP_SET_FPREG: u8 = 3;
+const UWOP_SAVE_NONVOL: u8 = 4;
+const UWOP_SAVE_NONVOL_FAR: u8 = 5;
+const UWOP_SAVE_XMM128: u8 = 8;
+const UWOP_SAVE_XMM128_FAR: u8 = 9;
+const UWOP_PUSH_MACHFRAME: u8 = 10;
+
+/// x64 stack unwinder seeded from a thread's saved `RIP`/`RSP`.
+pub struct StackWalker<'a> {
+    module_base: Address,
+    functions: &'a [RuntimeFunction],
+    symbols: &'a Win32Symbols,
+    /// maximum number of frames to resolve before giving up
+    max_frames: usize,
+}
+
+impl<'a> StackWalker<'a> {
+    pub fn new(
+        module_base: Address,
+        functions: &'a [RuntimeFunction],
+        symbols: &'a Win32Symbols,
+    ) -> Self {
+        Self {
+            module_base,
+            functions,
+            symbols,
+            max_frames: 256,
+        }
+    }
+
+    pub fn max_frames(mut self, max: usize) -> Self {
+        self.max_frames = max;
+        self
+    }
+
+    /// Walk the stack starting at `rip`/`rsp`, reading stack memory from `mem`.
+    pub fn walk<T: VirtualMemory>(
+        &self,
+        mem: &mut T,
+        mut rip: Address,
+        mut rsp: Address,
+    ) -> Result<Vec<StackFrame>> {
+        let mut frames = Vec::new();
+
+        for _ in 0..self.max_frames {
+            frames.push(StackFrame {
+                ret_addr: rip,
+                symbol: self.resolve(rip),
+            });
+
+            let rva = (rip.as_u64().wrapping_sub(self.module_base.as_u64())) as u32;
+            let func = match self.functions.iter().find(|f| f.covers(rva)) {
+                Some(f) => f,
+                // no unwind info: assume a leaf frame, stop
+                None => break,
+            };
+
+            // replay the prologue to learn how much RSP was adjusted
+            let alloc = self.frame_alloc(mem, func)?;
+            rsp = rsp + alloc;
+
+            // pop the return address
+            let mut ret = [0u8; 8];
+            if mem.virt_read_raw_into(rsp, &mut ret).is_err() {
+                break;
+            }
+            let next = u64::from_le_bytes(ret);
+            rsp = rsp + 8usize;
+            if next == 0 {
+                break;
+            }
+            rip = Address::from(next);
+        }
+
+        Ok(frames)
+    }
+
+    /// Compute the number of bytes the function's prologue subtracted from RSP
+    /// (pushes + stack allocations) by replaying its `UNWIND_INFO` codes.
+    fn frame_alloc<T: VirtualMemory>(
+        &self,
+        mem: &mut T,
+        func: &RuntimeFunction,
+    ) -> Result<usize> {
+        let info_addr = self.module_base + func.unwind_info as usize;
+
+        let mut header = [0u8; 4];
+        mem.virt_read_raw_into(info_addr, &mut header)
+            .map_err(|_| Error::Other("unable to read UNWIND_INFO"))?;
+        let count_of_codes = header[2] as usize;
+
+        let mut codes = vec![0u8; count_of_codes * 2];
+        mem.virt_read_raw_into(info_addr + 4usize, &mut codes)
+            .map_err(|_| Error::Other("unable to read unwind codes"))?;
+
+        let mut alloc = 0usize;
+        let mut i = 0;
+        while i < count_of_codes {
+            let op = codes[i * 2 + 1] & 0x0f;
+            let op_info = codes[i * 2 + 1] >> 4;
+            match op {
+                UWOP_PUSH_NONVOL => {
+                    alloc += 8;
+                    i += 1;
+                }
+                UWOP_ALLOC_SMALL => {
+                    alloc += (op_info as usize) * 8 + 8;
+                    i += 1;
+                }
+                UWOP_ALLOC_LARGE => {
+                    if op_info == 0 {
+                        let slot = u16::from_le_bytes([codes[(i + 1) * 2], codes[(i + 1) * 2 + 1]]);
+                        alloc += slot as usize * 8;
+                        i += 2;
+                    } else {
+                        let slot = u32::from_le_bytes([
+                            codes[(i + 1) * 2],
+                            codes[(i + 1) * 2 + 1],
+                            codes[(i + 2) * 2],
+                            codes[(i + 2) * 2 + 1],
+                        ]);
+                        alloc += slot as usize;
+                        i += 3;
+                    }
+                }
+                UWOP_SET_FPREG => i += 1,
+                UWOP_SAVE_NONVOL | UWOP_SAVE_XMM128 => i += 2,
+                UWOP_SAVE_NONVOL_FAR | UWOP_SAVE_XMM128_FAR => i += 3,
+                UWOP_PUSH_MACHFRAME => {
+                    alloc += if op_info == 0 { 40 } else { 48 };
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+
+        Ok(alloc)
+    }
+
+    /// Map a return address back to `symbol+0x..` via the symbol table; the
+    /// module name is not included.
+    fn resolve(&self, addr: Address) -> Option<String> {
+        let rva = addr.as_u64().checked_sub(self.module_base.as_u64())? as u32;
+        // the symbol table is keyed by RVA; pick the closest symbol at or below
+        let mut best: Option<(&str, u32)> = None;
+        for (name, &sym_rva) in self.symbols.iter() {
+            if sym_rva <= rva && best.map_or(true, |(_, b)| sym_rva > b) {
+                best = Some((name, sym_rva));
+            }
+        }
+        best.map(|(name, sym_rva)| format!("{}+0x{:x}", name, rva - sym_rva))
+    }
+}
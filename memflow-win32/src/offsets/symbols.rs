@@ -0,0 +1,117 @@
+/*!
+Resolve kernel function/global RVAs from a PDB.
+
+[`PdbStruct`](super::pdb_struct::PdbStruct) only resolves struct member offsets.
+Many consumers also need the relative virtual addresses of exported globals such
+as `PsActiveProcessHead`, `PsLoadedModuleList` or `KeServiceDescriptorTable`,
+which otherwise have to be hard-coded or signature-scanned. This module parses
+the PDB global and public symbol streams and exposes a `name -> RVA` lookup,
+letting the builder hand back the kernel-relative addresses needed to bootstrap
+traversal without relying on the PEB/module walk (useful when the PEB is paged
+out).
+*/
+
+use std::prelude::v1::*;
+
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use crate::error::{Error, Result};
+
+use pdb::{FallibleIterator, SymbolData, PDB};
+
+/// A `name -> RVA` lookup parsed from a PDB's symbol streams.
+pub struct PdbSymbols {
+    symbols: HashMap<String, u32>,
+}
+
+impl PdbSymbols {
+    /// Parse the global and public symbol streams of a PDB slice.
+    pub fn with(pdb_slice: &[u8]) -> Result<Self> {
+        let mut pdb = PDB::open(Cursor::new(pdb_slice))
+            .map_err(|_| Error::PDB("unable to parse pdb for symbols"))?;
+
+        let address_map = pdb
+            .address_map()
+            .map_err(|_| Error::PDB("pdb has no address map"))?;
+        let symbol_table = pdb
+            .global_symbols()
+            .map_err(|_| Error::PDB("pdb has no global symbol stream"))?;
+
+        let mut symbols = HashMap::new();
+        let mut iter = symbol_table.iter();
+        while let Some(symbol) = iter
+            .next()
+            .map_err(|_| Error::PDB("failed to iterate pdb symbols"))?
+        {
+            // both public and global data symbols carry a segmented address
+            // which we convert into an image-relative RVA
+            let (name, address) = match symbol.parse() {
+                Ok(SymbolData::Public(data)) => (data.name, data.offset),
+                Ok(SymbolData::Data(data)) => (data.name, data.offset),
+                Ok(SymbolData::Procedure(data)) => (data.name, data.offset),
+                _ => continue,
+            };
+
+            if let Some(rva) = address.to_rva(&address_map) {
+                symbols.insert(name.to_string().into_owned(), rva.0);
+            }
+        }
+
+        Ok(Self { symbols })
+    }
+
+    /// Look up the RVA of a single symbol.
+    pub fn find(&self, name: &str) -> Option<u32> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Resolve a caller-supplied set of symbol names.
+    ///
+    /// Returns a [`Win32Symbols`] holding the resolved RVAs together with the
+    /// list of names that were *not* found, so callers can degrade gracefully on
+    /// stripped PDBs.
+    pub fn resolve(&self, names: &[&str]) -> Win32Symbols {
+        let mut resolved = HashMap::new();
+        let mut missing = Vec::new();
+        for &name in names {
+            match self.find(name) {
+                Some(rva) => {
+                    resolved.insert(name.to_string(), rva);
+                }
+                None => missing.push(name.to_string()),
+            }
+        }
+        Win32Symbols { resolved, missing }
+    }
+}
+
+/// Resolved RVAs for a configurable set of kernel globals.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Win32Symbols {
+    resolved: HashMap<String, u32>,
+    missing: Vec<String>,
+}
+
+impl Win32Symbols {
+    /// RVA of a previously requested symbol, if it was present in the PDB.
+    pub fn rva(&self, name: &str) -> Option<u32> {
+        self.resolved.get(name).copied()
+    }
+
+    /// Names that were requested but not found in the PDB.
+    pub fn missing(&self) -> &[String] {
+        &self.missing
+    }
+
+    /// Whether every requested symbol was resolved.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    /// Iterate over the resolved `(name, rva)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &u32)> {
+        self.resolved.iter()
+    }
+}
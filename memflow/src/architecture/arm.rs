@@ -0,0 +1,285 @@
+/*!
+AArch64 architecture with a multi-granule, multi-level page-table walker.
+
+This module mirrors the `riscv` and `x86` sub-modules: it exposes an
+[`Architecture`] per supported translation granule (4 KiB, 16 KiB and 64 KiB)
+and a [`VirtualTranslate3`] implementation that descends the stage-1 page tables
+pointed at by the `TTBR` translation base.
+
+The walk geometry is selected by the granule, assuming a 48-bit virtual address
+space (`T0SZ == 16`):
+
+| granule | offset bits | index bits | levels | top-level index |
+|---------|-------------|------------|--------|-----------------|
+| 4 KiB   | 12          | 9          | 4      | 9 bit           |
+| 16 KiB  | 14          | 11         | 4      | 1 bit           |
+| 64 KiB  | 16          | 13         | 3      | 6 bit           |
+
+For an `N`-level walk we start at the top level, index the current table with
+`(vaddr >> (offset + index_bits*level)) & mask` and read the 8-byte descriptor.
+Bit 0 is the valid bit; above the final level bit 1 selects a table descriptor
+(`1`) over a block descriptor (`0`), so a block found above level 0 is a large
+page whose output address covers the remaining low bits of the virtual address.
+*/
+
+use super::{Architecture, ArchitectureIdent, ArchitectureObj, Endianess, VirtualTranslate3};
+
+use crate::error::{Error, ErrorKind, ErrorOrigin, Result};
+use crate::iter::SplitAtIndex;
+use crate::mem::PhysicalMemory;
+use crate::types::{size, Address, PhysicalAddress};
+
+/// AArch64 translation granule, selecting the walk geometry.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Granule {
+    /// 4 KiB granule: 12-bit page offset, 9-bit table indices.
+    Kb4,
+    /// 16 KiB granule: 14-bit page offset, 11-bit table indices.
+    Kb16,
+    /// 64 KiB granule: 16-bit page offset, 13-bit table indices.
+    Kb64,
+}
+
+impl Granule {
+    /// Resolves a granule from its page size in bytes, if supported.
+    fn from_page_size(page_size: usize) -> Option<Self> {
+        match page_size {
+            p if p == size::kb(4) => Some(Granule::Kb4),
+            p if p == size::kb(16) => Some(Granule::Kb16),
+            p if p == size::kb(64) => Some(Granule::Kb64),
+            _ => None,
+        }
+    }
+
+    /// Number of bits addressing a byte within a page (the page offset width).
+    fn page_bits(self) -> u32 {
+        match self {
+            Granule::Kb4 => 12,
+            Granule::Kb16 => 14,
+            Granule::Kb64 => 16,
+        }
+    }
+
+    /// Width in bits of a single (non top-level) table index.
+    fn index_bits(self) -> u32 {
+        match self {
+            Granule::Kb4 => 9,
+            Granule::Kb16 => 11,
+            Granule::Kb64 => 13,
+        }
+    }
+
+    /// Size of the page in bytes.
+    fn page_size(self) -> usize {
+        1usize << self.page_bits()
+    }
+
+    /// Width of the translated virtual address (`64 - T0SZ`).
+    fn va_bits(self) -> u32 {
+        48
+    }
+
+    /// Number of translation levels in the walk.
+    fn levels(self) -> u32 {
+        // ceil((va_bits - page_bits) / index_bits)
+        let resolved = self.va_bits() - self.page_bits();
+        (resolved + self.index_bits() - 1) / self.index_bits()
+    }
+
+    /// Width of the (possibly narrower) top-level table index.
+    fn top_index_bits(self) -> u32 {
+        let resolved = self.va_bits() - self.page_bits();
+        resolved - self.index_bits() * (self.levels() - 1)
+    }
+}
+
+/// An AArch64 architecture definition for a particular granule.
+pub struct AArch64Architecture {
+    granule: Granule,
+}
+
+impl Architecture for AArch64Architecture {
+    fn bits(&self) -> u8 {
+        64
+    }
+
+    fn endianess(&self) -> Endianess {
+        // AArch64 page-table walks are always performed little-endian
+        Endianess::LittleEndian
+    }
+
+    fn page_size(&self) -> usize {
+        self.granule.page_size()
+    }
+
+    fn size_addr(&self) -> usize {
+        8
+    }
+
+    fn address_space_bits(&self) -> u8 {
+        self.granule.va_bits() as u8
+    }
+
+    fn ident(&self) -> ArchitectureIdent {
+        ArchitectureIdent::AArch64(self.granule.page_size())
+    }
+}
+
+/// AArch64 architecture objects for the 4 KiB, 16 KiB and 64 KiB granules.
+pub mod aarch64 {
+    use super::{AArch64Architecture, ArchitectureObj, Granule};
+
+    static ARCH_SPEC_4K: AArch64Architecture = AArch64Architecture {
+        granule: Granule::Kb4,
+    };
+    static ARCH_SPEC_16K: AArch64Architecture = AArch64Architecture {
+        granule: Granule::Kb16,
+    };
+    static ARCH_SPEC_64K: AArch64Architecture = AArch64Architecture {
+        granule: Granule::Kb64,
+    };
+
+    /// The AArch64 architecture object for the default 4 KiB granule.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC_4K;
+    /// The AArch64 architecture object for the 16 KiB granule.
+    pub const ARCH_16K: ArchitectureObj = &ARCH_SPEC_16K;
+    /// The AArch64 architecture object for the 64 KiB granule.
+    pub const ARCH_64K: ArchitectureObj = &ARCH_SPEC_64K;
+
+    /// Returns the architecture object for the given translation granule
+    /// (expressed as a page size in bytes), or `None` if the granule is not one
+    /// of the three architecturally defined sizes.
+    pub fn arch_with_granule(page_size: usize) -> Option<ArchitectureObj> {
+        match Granule::from_page_size(page_size)? {
+            Granule::Kb4 => Some(ARCH),
+            Granule::Kb16 => Some(ARCH_16K),
+            Granule::Kb64 => Some(ARCH_64K),
+        }
+    }
+}
+
+/// Translates virtual addresses for a single AArch64 address space.
+#[derive(Debug, Clone, Copy)]
+pub struct AArch64VirtualTranslate {
+    granule: Granule,
+    /// physical address of the root translation table (`TTBR`)
+    dtb: Address,
+}
+
+impl AArch64VirtualTranslate {
+    pub fn new(granule: Granule, dtb: Address) -> Self {
+        Self { granule, dtb }
+    }
+
+    /// Walks the translation tables for a single virtual address.
+    fn walk<T: PhysicalMemory + ?Sized>(
+        &self,
+        mem: &mut T,
+        vaddr: Address,
+    ) -> Result<PhysicalAddress> {
+        let page_bits = self.granule.page_bits();
+        let index_bits = self.granule.index_bits();
+        let top_index_bits = self.granule.top_index_bits();
+        let levels = self.granule.levels();
+
+        // output addresses occupy bits [0, 48) of a descriptor
+        let oa_mask = (1u64 << self.granule.va_bits()) - 1;
+
+        let mut table = self.dtb;
+        for level in (0..levels).rev() {
+            let width = if level == levels - 1 {
+                top_index_bits
+            } else {
+                index_bits
+            };
+            let shift = page_bits + index_bits * level;
+            let index = (vaddr.as_u64() >> shift) & ((1u64 << width) - 1);
+            let desc_addr = table + (index as usize * 8);
+
+            let mut buf = [0u8; 8];
+            mem.phys_read_raw_into(desc_addr.into(), &mut buf)?;
+            let desc = u64::from_le_bytes(buf);
+
+            // bit 0: valid
+            if desc & 0b1 == 0 {
+                return Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds));
+            }
+
+            // bit 1: table descriptor (above the final level) vs block descriptor
+            let is_table = desc & 0b10 != 0;
+
+            if level > 0 && is_table {
+                // pointer to the next-level table, aligned to the page size
+                table = Address::from(desc & oa_mask & !((1u64 << page_bits) - 1));
+                continue;
+            }
+
+            // a page descriptor at the final level must have bit 1 set
+            if level == 0 && !is_table {
+                return Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds));
+            }
+
+            // leaf: compose the output address from the descriptor base and the
+            // low `shift` bits of the virtual address
+            let base = desc & oa_mask & !((1u64 << shift) - 1);
+            let offset = vaddr.as_u64() & ((1u64 << shift) - 1);
+            return Ok(PhysicalAddress::from(base + offset));
+        }
+
+        Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds))
+    }
+}
+
+impl VirtualTranslate3 for AArch64VirtualTranslate {
+    fn virt_to_phys_iter<
+        T: PhysicalMemory + ?Sized,
+        B: SplitAtIndex,
+        VI: Iterator<Item = (Address, B)>,
+        VO: Extend<(PhysicalAddress, B)>,
+        FO: Extend<(Error, Address, B)>,
+    >(
+        &self,
+        mem: &mut T,
+        addrs: VI,
+        out: &mut VO,
+        out_fail: &mut FO,
+        _tmp_buf: &mut [std::mem::MaybeUninit<u8>],
+    ) {
+        for (addr, buf) in addrs {
+            match self.walk(mem, addr) {
+                Ok(paddr) => out.extend(Some((paddr, buf))),
+                Err(err) => out_fail.extend(Some((err, addr, buf))),
+            }
+        }
+    }
+
+    fn translation_table_id(&self, _address: Address) -> usize {
+        self.dtb.as_u64().overflowing_shr(self.granule.page_bits()).0 as usize
+    }
+
+    fn arch(&self) -> ArchitectureObj {
+        match self.granule {
+            Granule::Kb4 => aarch64::ARCH,
+            Granule::Kb16 => aarch64::ARCH_16K,
+            Granule::Kb64 => aarch64::ARCH_64K,
+        }
+    }
+}
+
+/// Creates a translator for the given `arch`, descending from the translation
+/// table at `dtb` (the `TTBR` root).
+///
+/// Named to match the kernel VAT fallback (`arm::new_translator_nonsplit`),
+/// mirroring the sibling [`x86::new_translator`](super::x86::new_translator)
+/// entry point.
+pub fn new_translator_nonsplit(
+    dtb: Address,
+    arch: ArchitectureObj,
+) -> Result<AArch64VirtualTranslate> {
+    let granule = match arch.ident() {
+        ArchitectureIdent::AArch64(page_size) => Granule::from_page_size(page_size)
+            .ok_or(Error(ErrorOrigin::Mmu, ErrorKind::InvalidArchitecture))?,
+        _ => return Err(Error(ErrorOrigin::Mmu, ErrorKind::InvalidArchitecture)),
+    };
+    Ok(AArch64VirtualTranslate::new(granule, dtb))
+}
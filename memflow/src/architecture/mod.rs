@@ -14,6 +14,8 @@ that memflow know the proper byte order of the target system.
 */
 
 pub mod arm;
+pub mod powerpc;
+pub mod riscv;
 pub mod x86;
 #[macro_use]
 mod mmu;
@@ -130,6 +132,76 @@ pub trait VirtualTranslate3: Clone + Copy + Send {
     fn translation_table_id(&self, address: Address) -> usize;
 
     fn arch(&self) -> ArchitectureObj;
+
+    /// Translates every page in the virtual range `[start, end)` and returns the
+    /// resolved mappings coalesced into physically-contiguous spans.
+    ///
+    /// Each returned tuple is `(virtual_start, physical_start, len)` describing a
+    /// run of `len` bytes that is contiguous in both the virtual and physical
+    /// address space. Unmapped pages simply break the current span, so a process'
+    /// full valid mapping set can be dumped in a single pass instead of
+    /// translating address-by-address.
+    fn virt_to_phys_range<T: PhysicalMemory>(
+        &self,
+        mem: &mut T,
+        start: Address,
+        end: Address,
+    ) -> Vec<(Address, PhysicalAddress, usize)> {
+        let page_size = self.arch().page_size();
+
+        let mut spans: Vec<(Address, PhysicalAddress, usize)> = Vec::new();
+        let mut vaddr = start.as_page_aligned(page_size);
+        while vaddr < end {
+            // an unmapped page simply breaks the current contiguous span
+            if let Ok(paddr) = self.virt_to_phys(mem, vaddr) {
+                Self::push_span(&mut spans, vaddr, paddr, page_size);
+            }
+            vaddr += page_size;
+        }
+
+        spans
+    }
+
+    /// Translates a scattered list of virtual addresses, coalescing any results
+    /// that happen to be physically contiguous into shared spans.
+    ///
+    /// Like [`virt_to_phys_range`](Self::virt_to_phys_range) the result is a list
+    /// of `(virtual_start, physical_start, len)` tuples; addresses that fail to
+    /// translate are dropped from the output.
+    fn virt_to_phys_scatter<T: PhysicalMemory>(
+        &self,
+        mem: &mut T,
+        addrs: &[Address],
+    ) -> Vec<(Address, PhysicalAddress, usize)> {
+        let page_size = self.arch().page_size();
+
+        let mut spans: Vec<(Address, PhysicalAddress, usize)> = Vec::new();
+        for &addr in addrs {
+            let vaddr = addr.as_page_aligned(page_size);
+            if let Ok(paddr) = self.virt_to_phys(mem, vaddr) {
+                Self::push_span(&mut spans, vaddr, paddr, page_size);
+            }
+        }
+
+        spans
+    }
+
+    /// Appends `(vaddr, paddr, len)` to `spans`, merging it into the previous
+    /// span when both the virtual and physical addresses continue it.
+    fn push_span(
+        spans: &mut Vec<(Address, PhysicalAddress, usize)>,
+        vaddr: Address,
+        paddr: PhysicalAddress,
+        len: usize,
+    ) {
+        if let Some(last) = spans.last_mut() {
+            if last.0 + last.2 == vaddr && last.1.address + last.2 == paddr.address {
+                last.2 += len;
+                return;
+            }
+        }
+        spans.push((vaddr, paddr, len));
+    }
 }
 
 pub trait Architecture: Send + Sync + 'static {
@@ -244,12 +316,21 @@ pub enum ArchitectureIdent {
     ///
     /// First argument - `bitness` controls whether it's 32, or 64 bit variant.
     /// Second argument - `address_extensions` control whether address extensions are
-    /// enabled (PAE on x32, or LA57 on x64). Warning: LA57 is currently unsupported.
+    /// enabled (PAE on x32, or LA57 on x64).
     X86(u8, bool),
     /// Arm 64-bit architecture with specified page size
     ///
-    /// Valid page sizes are 4kb, 16kb, 64kb. Only 4kb is supported at the moment
+    /// Valid page sizes (translation granules) are 4kb, 16kb and 64kb, all of
+    /// which are supported.
     AArch64(usize),
+    /// RISC-V architecture with specified bitness and paging mode.
+    ///
+    /// First argument - `bits` controls whether it's the 32 or 64 bit variant.
+    /// Second argument - `mode` is the SATP paging mode: `32` selects Sv32,
+    /// `39` selects Sv39 and `48` selects Sv48.
+    RiscV { bits: u8, mode: u8 },
+    /// Big-endian PowerPC architecture with specified bitness (32 or 64).
+    PowerPc(u8),
 }
 
 impl std::fmt::Display for ArchitectureIdent {
@@ -261,6 +342,11 @@ impl std::fmt::Display for ArchitectureIdent {
             ArchitectureIdent::X86(64, true) => f.pad("x86_64 LA57"),
             ArchitectureIdent::X86(_, _) => f.pad("x86"),
             ArchitectureIdent::AArch64(_) => f.pad("AArch64"),
+            ArchitectureIdent::RiscV { bits: 32, .. } => f.pad("RISC-V Sv32"),
+            ArchitectureIdent::RiscV { mode: 48, .. } => f.pad("RISC-V Sv48"),
+            ArchitectureIdent::RiscV { .. } => f.pad("RISC-V Sv39"),
+            ArchitectureIdent::PowerPc(32) => f.pad("PowerPC 32"),
+            ArchitectureIdent::PowerPc(_) => f.pad("PowerPC 64"),
             ArchitectureIdent::Unknown => f.pad("Unknown"),
         }
     }
@@ -274,12 +360,18 @@ impl ArchitectureIdent {
 
 impl From<ArchitectureIdent> for ArchitectureObj {
     fn from(arch: ArchitectureIdent) -> ArchitectureObj {
-        const KB4: usize = size::kb(4);
         match arch {
             ArchitectureIdent::X86(32, false) => x86::x32::ARCH,
             ArchitectureIdent::X86(32, true) => x86::x32_pae::ARCH,
             ArchitectureIdent::X86(64, false) => x86::x64::ARCH,
-            ArchitectureIdent::AArch64(KB4) => arm::aarch64::ARCH,
+            ArchitectureIdent::X86(64, true) => x86::x64_la57::ARCH,
+            ArchitectureIdent::AArch64(page_size) => arm::aarch64::arch_with_granule(page_size)
+                .unwrap_or_else(|| panic!("unsupported architecture! {:?}", arch)),
+            ArchitectureIdent::RiscV { bits: 32, .. } => riscv::sv32::ARCH,
+            ArchitectureIdent::RiscV { mode: 48, .. } => riscv::sv48::ARCH,
+            ArchitectureIdent::RiscV { .. } => riscv::sv39::ARCH,
+            ArchitectureIdent::PowerPc(32) => powerpc::ppc32::ARCH,
+            ArchitectureIdent::PowerPc(_) => powerpc::ppc64::ARCH,
             _ => panic!("unsupported architecture! {:?}", arch),
         }
     }
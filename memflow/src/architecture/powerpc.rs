@@ -0,0 +1,215 @@
+/*!
+Big-endian PowerPC architecture.
+
+Every other architecture in memflow is little-endian, which left the
+[`Endianess::BigEndian`](super::Endianess) branch dead and the read/write paths
+implicitly assuming little-endian. PowerPC is the canonical big-endian target,
+so this module both adds a 32/64-bit PowerPC [`Architecture`] and makes the
+translation path honor [`Architecture::endianess`] when decoding page-table
+entries: descriptor PPN/flag fields read during a walk are byte-swapped when the
+target reports [`Endianess::BigEndian`].
+
+The walk itself is a straightforward radix descent (9-bit indices, 8-byte
+entries); the point of the module is to exercise the endianess-aware marshaling
+rather than to model every historical PowerPC MMU.
+*/
+
+use super::{Architecture, ArchitectureIdent, ArchitectureObj, Endianess, VirtualTranslate3};
+
+use crate::error::{Error, ErrorKind, ErrorOrigin, Result};
+use crate::iter::SplitAtIndex;
+use crate::mem::PhysicalMemory;
+use crate::types::{size, Address, PhysicalAddress};
+
+const LEVELS: usize = 4;
+const VPN_BITS: u32 = 9;
+const VPN_MASK: u64 = (1 << VPN_BITS) - 1;
+const PTE_SIZE: usize = 8;
+
+/// A PowerPC architecture definition.
+pub struct PowerPcArchitecture {
+    bits: u8,
+}
+
+impl Architecture for PowerPcArchitecture {
+    fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    fn endianess(&self) -> Endianess {
+        Endianess::BigEndian
+    }
+
+    fn page_size(&self) -> usize {
+        size::kb(4)
+    }
+
+    fn size_addr(&self) -> usize {
+        self.bits as usize / 8
+    }
+
+    fn address_space_bits(&self) -> u8 {
+        if self.bits == 64 {
+            56
+        } else {
+            32
+        }
+    }
+
+    fn ident(&self) -> ArchitectureIdent {
+        ArchitectureIdent::PowerPc(self.bits)
+    }
+}
+
+/// 32-bit PowerPC.
+pub mod ppc32 {
+    use super::{ArchitectureObj, PowerPcArchitecture};
+    static ARCH_SPEC: PowerPcArchitecture = PowerPcArchitecture { bits: 32 };
+    /// The 32-bit PowerPC architecture object.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC;
+}
+
+/// 64-bit PowerPC.
+pub mod ppc64 {
+    use super::{ArchitectureObj, PowerPcArchitecture};
+    static ARCH_SPEC: PowerPcArchitecture = PowerPcArchitecture { bits: 64 };
+    /// The 64-bit PowerPC architecture object.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC;
+}
+
+/// Translates virtual addresses for a single PowerPC address space.
+#[derive(Debug, Clone, Copy)]
+pub struct PowerPcVirtualTranslate {
+    arch: ArchitectureObj,
+    dtb: Address,
+}
+
+impl PowerPcVirtualTranslate {
+    pub fn new(arch: ArchitectureObj, dtb: Address) -> Self {
+        Self { arch, dtb }
+    }
+
+    fn walk<T: PhysicalMemory + ?Sized>(
+        &self,
+        mem: &mut T,
+        vaddr: Address,
+    ) -> Result<PhysicalAddress> {
+        let mut table = self.dtb;
+        for level in (0..LEVELS).rev() {
+            let index = (vaddr.as_u64() >> (12 + VPN_BITS * level as u32)) & VPN_MASK;
+            let pte = self.read_pte(mem, table + (index as usize * PTE_SIZE))?;
+
+            // present bit
+            if pte & 0b1 == 0 {
+                return Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds));
+            }
+
+            let ppn = pte >> 12;
+            // a leaf carries the page bit; otherwise descend to the next table
+            if pte & 0b10 != 0 || level == 0 {
+                let page_shift = 12 + VPN_BITS * level as u32;
+                let page_base = (ppn >> (VPN_BITS * level as u32)) << page_shift;
+                let offset = vaddr.as_u64() & ((1u64 << page_shift) - 1);
+                return Ok(PhysicalAddress::from(page_base + offset));
+            }
+
+            table = Address::from(ppn << 12);
+        }
+
+        Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds))
+    }
+
+    /// Reads an 8-byte PTE, decoding it according to the architecture endianess.
+    ///
+    /// This is what closes the loop on the big-endian branch: on a
+    /// [`Endianess::BigEndian`] target the descriptor is byte-swapped so its
+    /// PPN/flag fields land in the right place.
+    fn read_pte<T: PhysicalMemory + ?Sized>(&self, mem: &mut T, addr: Address) -> Result<u64> {
+        let mut buf = [0u8; PTE_SIZE];
+        mem.phys_read_raw_into(addr.into(), &mut buf)?;
+        Ok(match self.arch.endianess() {
+            Endianess::BigEndian => u64::from_be_bytes(buf),
+            Endianess::LittleEndian => u64::from_le_bytes(buf),
+        })
+    }
+}
+
+impl VirtualTranslate3 for PowerPcVirtualTranslate {
+    fn virt_to_phys_iter<
+        T: PhysicalMemory + ?Sized,
+        B: SplitAtIndex,
+        VI: Iterator<Item = (Address, B)>,
+        VO: Extend<(PhysicalAddress, B)>,
+        FO: Extend<(Error, Address, B)>,
+    >(
+        &self,
+        mem: &mut T,
+        addrs: VI,
+        out: &mut VO,
+        out_fail: &mut FO,
+        _tmp_buf: &mut [std::mem::MaybeUninit<u8>],
+    ) {
+        for (addr, buf) in addrs {
+            match self.walk(mem, addr) {
+                Ok(paddr) => out.extend(Some((paddr, buf))),
+                Err(err) => out_fail.extend(Some((err, addr, buf))),
+            }
+        }
+    }
+
+    fn translation_table_id(&self, _address: Address) -> usize {
+        self.dtb.as_u64().overflowing_shr(12).0 as usize
+    }
+
+    fn arch(&self) -> ArchitectureObj {
+        self.arch
+    }
+}
+
+/// Creates a translator for the given PowerPC `arch`, rooted at `dtb`.
+pub fn new_translator(dtb: Address, arch: ArchitectureObj) -> Result<PowerPcVirtualTranslate> {
+    match arch.ident() {
+        ArchitectureIdent::PowerPc(_) => Ok(PowerPcVirtualTranslate::new(arch, dtb)),
+        _ => Err(Error(ErrorOrigin::Mmu, ErrorKind::InvalidArchitecture)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dummy::DummyMemory;
+    use crate::mem::PhysicalMemory;
+    use crate::types::size;
+
+    /// Writes a big-endian PTE pointing one level down (or to a leaf).
+    fn write_pte(mem: &mut DummyMemory, table: Address, index: u64, value: u64) {
+        let addr = table + (index as usize * PTE_SIZE);
+        mem.phys_write_raw_into(addr.into(), &value.to_be_bytes()).unwrap();
+    }
+
+    #[test]
+    fn big_endian_round_trip() {
+        let mut mem = DummyMemory::new(size::mb(4));
+
+        // lay out a 4-level identity mapping for a single virtual page using
+        // big-endian descriptors, each table on its own physical page
+        let root = Address::from(0x1000u64);
+        let l2 = Address::from(0x2000u64);
+        let l1 = Address::from(0x3000u64);
+        let l0 = Address::from(0x4000u64);
+        let target = Address::from(0x5000u64);
+
+        let vaddr = Address::from(0u64);
+
+        // present, pointer entries (bit0 set, leaf bit clear) down to level 0
+        write_pte(&mut mem, root, 0, ((l2.as_u64() >> 12) << 12) | 0b1);
+        write_pte(&mut mem, l2, 0, ((l1.as_u64() >> 12) << 12) | 0b1);
+        write_pte(&mut mem, l1, 0, ((l0.as_u64() >> 12) << 12) | 0b1);
+        // leaf: present
+        write_pte(&mut mem, l0, 0, ((target.as_u64() >> 12) << 12) | 0b1);
+
+        let translator = PowerPcVirtualTranslate::new(ppc64::ARCH, root);
+        let phys = translator.virt_to_phys(&mut mem, vaddr).unwrap();
+        assert_eq!(phys.address, target);
+    }
+}
@@ -0,0 +1,281 @@
+/*!
+RISC-V architecture with an SATP-style multi-level page-table walker.
+
+This module mirrors the `arm` and `x86` sub-modules: it exposes an
+[`Architecture`] per supported paging mode (Sv32/Sv39/Sv48) and a
+[`VirtualTranslate3`] implementation that descends the page tables pointed at by
+the SATP translation base.
+
+The walk geometry is selected by the mode:
+
+| mode | levels | VPN bits | PTE size | physical addr |
+|------|--------|----------|----------|---------------|
+| Sv32 | 2      | 10       | 4        | 34 bit        |
+| Sv39 | 3      | 9        | 8        | 56 bit        |
+| Sv48 | 4      | 9        | 8        | 56 bit        |
+
+For an `N`-level walk we start at level `N-1`, index the current table with
+`VPN[level] = (vaddr >> (12 + bits*level)) & mask`, read the PTE and check the
+`V` bit. If `R`, `W` and `X` are all clear the entry points at the next table;
+otherwise it is a leaf. A leaf found above level 0 is a superpage whose lower
+PPN bits must be zero.
+*/
+
+use super::{Architecture, ArchitectureIdent, ArchitectureObj, Endianess, VirtualTranslate3};
+
+use crate::error::{Error, ErrorKind, ErrorOrigin, Result};
+use crate::iter::SplitAtIndex;
+use crate::mem::PhysicalMemory;
+use crate::types::{size, Address, PhysicalAddress};
+
+/// SATP paging mode, selecting the walk geometry.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RiscVMode {
+    /// 32-bit, two-level, 10-bit VPN fields, 4-byte PTEs.
+    Sv32,
+    /// 64-bit, three-level, 9-bit VPN fields.
+    Sv39,
+    /// 64-bit, four-level, 9-bit VPN fields.
+    Sv48,
+}
+
+impl RiscVMode {
+    /// Number of translation levels in the walk.
+    fn levels(self) -> usize {
+        match self {
+            RiscVMode::Sv32 => 2,
+            RiscVMode::Sv39 => 3,
+            RiscVMode::Sv48 => 4,
+        }
+    }
+
+    /// Width in bits of a single VPN field.
+    fn vpn_bits(self) -> u32 {
+        match self {
+            RiscVMode::Sv32 => 10,
+            RiscVMode::Sv39 | RiscVMode::Sv48 => 9,
+        }
+    }
+
+    /// Size in bytes of a single page-table entry.
+    fn pte_size(self) -> usize {
+        match self {
+            RiscVMode::Sv32 => 4,
+            RiscVMode::Sv39 | RiscVMode::Sv48 => 8,
+        }
+    }
+
+    fn address_space_bits(self) -> u8 {
+        match self {
+            RiscVMode::Sv32 => 34,
+            RiscVMode::Sv39 | RiscVMode::Sv48 => 56,
+        }
+    }
+}
+
+/// A RISC-V architecture definition.
+pub struct RiscVArchitecture {
+    bits: u8,
+    mode: RiscVMode,
+}
+
+impl Architecture for RiscVArchitecture {
+    fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    fn endianess(&self) -> Endianess {
+        // all mainstream RISC-V application processors run little-endian
+        Endianess::LittleEndian
+    }
+
+    fn page_size(&self) -> usize {
+        size::kb(4)
+    }
+
+    fn size_addr(&self) -> usize {
+        self.bits as usize / 8
+    }
+
+    fn address_space_bits(&self) -> u8 {
+        self.mode.address_space_bits()
+    }
+
+    fn ident(&self) -> ArchitectureIdent {
+        ArchitectureIdent::RiscV {
+            bits: self.bits,
+            mode: match self.mode {
+                RiscVMode::Sv32 => 32,
+                RiscVMode::Sv39 => 39,
+                RiscVMode::Sv48 => 48,
+            },
+        }
+    }
+}
+
+/// Sv32 paging.
+pub mod sv32 {
+    use super::{ArchitectureObj, RiscVArchitecture, RiscVMode};
+    static ARCH_SPEC: RiscVArchitecture = RiscVArchitecture {
+        bits: 32,
+        mode: RiscVMode::Sv32,
+    };
+    /// The Sv32 architecture object.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC;
+}
+
+/// Sv39 paging.
+pub mod sv39 {
+    use super::{ArchitectureObj, RiscVArchitecture, RiscVMode};
+    static ARCH_SPEC: RiscVArchitecture = RiscVArchitecture {
+        bits: 64,
+        mode: RiscVMode::Sv39,
+    };
+    /// The Sv39 architecture object.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC;
+}
+
+/// Sv48 paging.
+pub mod sv48 {
+    use super::{ArchitectureObj, RiscVArchitecture, RiscVMode};
+    static ARCH_SPEC: RiscVArchitecture = RiscVArchitecture {
+        bits: 64,
+        mode: RiscVMode::Sv48,
+    };
+    /// The Sv48 architecture object.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC;
+}
+
+/// Translates virtual addresses for a single RISC-V address space.
+#[derive(Debug, Clone, Copy)]
+pub struct RiscVVirtualTranslate {
+    mode: RiscVMode,
+    /// physical address of the root page table (SATP.PPN << 12)
+    dtb: Address,
+}
+
+impl RiscVVirtualTranslate {
+    pub fn new(mode: RiscVMode, dtb: Address) -> Self {
+        Self { mode, dtb }
+    }
+
+    /// Walks the page tables for a single virtual address.
+    fn walk<T: PhysicalMemory + ?Sized>(
+        &self,
+        mem: &mut T,
+        vaddr: Address,
+    ) -> Result<PhysicalAddress> {
+        let vpn_bits = self.mode.vpn_bits();
+        let vpn_mask = (1u64 << vpn_bits) - 1;
+        let pte_size = self.mode.pte_size();
+        let levels = self.mode.levels();
+
+        let mut table = self.dtb;
+        for level in (0..levels).rev() {
+            let index = (vaddr.as_u64() >> (12 + vpn_bits * level as u32)) & vpn_mask;
+            let pte_addr = table + (index as usize * pte_size);
+
+            let pte = self.read_pte(mem, pte_addr, pte_size)?;
+
+            // V bit
+            if pte & 0b1 == 0 {
+                return Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds));
+            }
+
+            let readable = pte & 0b10 != 0;
+            let writable = pte & 0b100 != 0;
+            let executable = pte & 0b1000 != 0;
+
+            // an entry with none of R/W/X set is a pointer to the next table
+            if !(readable || writable || executable) {
+                let ppn = pte >> 10;
+                table = Address::from(ppn << 12);
+                continue;
+            }
+
+            // leaf entry: compose the physical address from the leaf PPN and the
+            // remaining low bits of the virtual address for this level
+            let ppn = pte >> 10;
+            let page_shift = 12 + vpn_bits * level as u32;
+
+            // a superpage must have its lower PPN bits cleared, otherwise the
+            // entry is malformed
+            let low_ppn_mask = (1u64 << (vpn_bits * level as u32)) - 1;
+            if ppn & low_ppn_mask != 0 {
+                return Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds));
+            }
+
+            let page_base = (ppn >> (vpn_bits * level as u32)) << page_shift;
+            let offset = vaddr.as_u64() & ((1u64 << page_shift) - 1);
+            return Ok(PhysicalAddress::from(page_base + offset));
+        }
+
+        Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds))
+    }
+
+    /// Reads a 4- or 8-byte PTE honoring the architecture endianess.
+    fn read_pte<T: PhysicalMemory + ?Sized>(
+        &self,
+        mem: &mut T,
+        addr: Address,
+        pte_size: usize,
+    ) -> Result<u64> {
+        let mut buf = [0u8; 8];
+        mem.phys_read_raw_into(addr.into(), &mut buf[..pte_size])?;
+        Ok(if pte_size == 4 {
+            let mut b = [0u8; 4];
+            b.copy_from_slice(&buf[..4]);
+            u32::from_le_bytes(b) as u64
+        } else {
+            u64::from_le_bytes(buf)
+        })
+    }
+}
+
+impl VirtualTranslate3 for RiscVVirtualTranslate {
+    fn virt_to_phys_iter<
+        T: PhysicalMemory + ?Sized,
+        B: SplitAtIndex,
+        VI: Iterator<Item = (Address, B)>,
+        VO: Extend<(PhysicalAddress, B)>,
+        FO: Extend<(Error, Address, B)>,
+    >(
+        &self,
+        mem: &mut T,
+        addrs: VI,
+        out: &mut VO,
+        out_fail: &mut FO,
+        _tmp_buf: &mut [std::mem::MaybeUninit<u8>],
+    ) {
+        for (addr, buf) in addrs {
+            match self.walk(mem, addr) {
+                Ok(paddr) => out.extend(Some((paddr, buf))),
+                Err(err) => out_fail.extend(Some((err, addr, buf))),
+            }
+        }
+    }
+
+    fn translation_table_id(&self, _address: Address) -> usize {
+        self.dtb.as_u64().overflowing_shr(12).0 as usize
+    }
+
+    fn arch(&self) -> ArchitectureObj {
+        match self.mode {
+            RiscVMode::Sv32 => sv32::ARCH,
+            RiscVMode::Sv39 => sv39::ARCH,
+            RiscVMode::Sv48 => sv48::ARCH,
+        }
+    }
+}
+
+/// Creates a translator for the given `arch`, descending from the page table at
+/// `dtb` (the SATP root).
+pub fn new_translator(dtb: Address, arch: ArchitectureObj) -> Result<RiscVVirtualTranslate> {
+    let mode = match arch.ident() {
+        ArchitectureIdent::RiscV { bits: 32, .. } => RiscVMode::Sv32,
+        ArchitectureIdent::RiscV { mode: 48, .. } => RiscVMode::Sv48,
+        ArchitectureIdent::RiscV { .. } => RiscVMode::Sv39,
+        _ => return Err(Error(ErrorOrigin::Mmu, ErrorKind::InvalidArchitecture)),
+    };
+    Ok(RiscVVirtualTranslate::new(mode, dtb))
+}
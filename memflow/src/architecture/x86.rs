@@ -0,0 +1,312 @@
+/*!
+x86 architecture with a multi-level page-table walker.
+
+This module mirrors the `arm` and `riscv` sub-modules: it exposes an
+[`Architecture`] per supported paging mode and a [`VirtualTranslate3`]
+implementation that descends the page tables pointed at by the `CR3`
+translation base.
+
+The walk geometry is selected by the mode:
+
+| mode      | levels | index bits | top index | PTE size | address bits |
+|-----------|--------|------------|-----------|----------|--------------|
+| x32       | 2      | 10         | 10        | 4        | 32           |
+| x32 PAE   | 3      | 9          | 2         | 8        | 52           |
+| x64       | 4      | 9          | 9         | 8        | 52           |
+| x64 LA57  | 5      | 9          | 9         | 8        | 52           |
+
+For an `N`-level walk we start at the top level, index the current table with
+`(vaddr >> (12 + index_bits*level)) & mask` and read the PTE. Bit 0 is the
+present bit; above the final level bit 7 (`PS`) marks a large-page leaf whose
+output address covers the remaining low bits of the virtual address.
+*/
+
+use super::{Architecture, ArchitectureIdent, ArchitectureObj, Endianess, VirtualTranslate3};
+
+use crate::error::{Error, ErrorKind, ErrorOrigin, Result};
+use crate::iter::SplitAtIndex;
+use crate::mem::PhysicalMemory;
+use crate::types::{size, Address, PhysicalAddress};
+
+/// x86 paging mode, selecting the walk geometry.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum X86Mode {
+    /// 32-bit, two-level, 10-bit indices, 4-byte PTEs.
+    X32,
+    /// 32-bit PAE, three-level, 8-byte PTEs with a 2-bit top index.
+    X32Pae,
+    /// 64-bit, four-level (PML4) paging.
+    X64,
+    /// 64-bit, five-level (PML5 / LA57) paging.
+    X64La57,
+}
+
+impl X86Mode {
+    /// Number of translation levels in the walk.
+    fn levels(self) -> u32 {
+        match self {
+            X86Mode::X32 => 2,
+            X86Mode::X32Pae => 3,
+            X86Mode::X64 => 4,
+            X86Mode::X64La57 => 5,
+        }
+    }
+
+    /// Width in bits of a single (non top-level) table index.
+    fn index_bits(self) -> u32 {
+        match self {
+            X86Mode::X32 => 10,
+            X86Mode::X32Pae | X86Mode::X64 | X86Mode::X64La57 => 9,
+        }
+    }
+
+    /// Width of the (possibly narrower) top-level table index.
+    fn top_index_bits(self) -> u32 {
+        match self {
+            X86Mode::X32 => 10,
+            // PAE tops out at a 2-bit page-directory-pointer index
+            X86Mode::X32Pae => 2,
+            X86Mode::X64 | X86Mode::X64La57 => 9,
+        }
+    }
+
+    /// Size in bytes of a single page-table entry.
+    fn pte_size(self) -> usize {
+        match self {
+            X86Mode::X32 => 4,
+            _ => 8,
+        }
+    }
+
+    fn bits(self) -> u8 {
+        match self {
+            X86Mode::X32 | X86Mode::X32Pae => 32,
+            X86Mode::X64 | X86Mode::X64La57 => 64,
+        }
+    }
+
+    fn address_space_bits(self) -> u8 {
+        match self {
+            X86Mode::X32 => 32,
+            X86Mode::X32Pae | X86Mode::X64 => 52,
+            // five-level paging widens the linear address space to 57 bits
+            X86Mode::X64La57 => 57,
+        }
+    }
+
+    /// Number of bits of physical output address a descriptor carries.
+    fn phys_bits(self) -> u32 {
+        match self {
+            X86Mode::X32 => 32,
+            _ => 52,
+        }
+    }
+}
+
+/// An x86 architecture definition.
+pub struct X86Architecture {
+    mode: X86Mode,
+}
+
+impl Architecture for X86Architecture {
+    fn bits(&self) -> u8 {
+        self.mode.bits()
+    }
+
+    fn endianess(&self) -> Endianess {
+        Endianess::LittleEndian
+    }
+
+    fn page_size(&self) -> usize {
+        size::kb(4)
+    }
+
+    fn size_addr(&self) -> usize {
+        self.mode.bits() as usize / 8
+    }
+
+    fn address_space_bits(&self) -> u8 {
+        self.mode.address_space_bits()
+    }
+
+    fn ident(&self) -> ArchitectureIdent {
+        match self.mode {
+            X86Mode::X32 => ArchitectureIdent::X86(32, false),
+            X86Mode::X32Pae => ArchitectureIdent::X86(32, true),
+            X86Mode::X64 => ArchitectureIdent::X86(64, false),
+            X86Mode::X64La57 => ArchitectureIdent::X86(64, true),
+        }
+    }
+}
+
+/// 32-bit paging.
+pub mod x32 {
+    use super::{ArchitectureObj, X86Architecture, X86Mode};
+    static ARCH_SPEC: X86Architecture = X86Architecture {
+        mode: X86Mode::X32,
+    };
+    /// The x86 32-bit architecture object.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC;
+}
+
+/// 32-bit PAE paging.
+pub mod x32_pae {
+    use super::{ArchitectureObj, X86Architecture, X86Mode};
+    static ARCH_SPEC: X86Architecture = X86Architecture {
+        mode: X86Mode::X32Pae,
+    };
+    /// The x86 32-bit PAE architecture object.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC;
+}
+
+/// 64-bit (PML4) paging.
+pub mod x64 {
+    use super::{ArchitectureObj, X86Architecture, X86Mode};
+    static ARCH_SPEC: X86Architecture = X86Architecture {
+        mode: X86Mode::X64,
+    };
+    /// The x86_64 architecture object.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC;
+}
+
+/// 64-bit five-level (PML5 / LA57) paging.
+pub mod x64_la57 {
+    use super::{ArchitectureObj, X86Architecture, X86Mode};
+    static ARCH_SPEC: X86Architecture = X86Architecture {
+        mode: X86Mode::X64La57,
+    };
+    /// The x86_64 LA57 (five-level paging) architecture object.
+    pub const ARCH: ArchitectureObj = &ARCH_SPEC;
+}
+
+/// Translates virtual addresses for a single x86 address space.
+#[derive(Debug, Clone, Copy)]
+pub struct X86VirtualTranslate {
+    mode: X86Mode,
+    /// physical address of the root page table (`CR3`)
+    dtb: Address,
+}
+
+impl X86VirtualTranslate {
+    pub fn new(mode: X86Mode, dtb: Address) -> Self {
+        Self { mode, dtb }
+    }
+
+    /// Walks the page tables for a single virtual address.
+    fn walk<T: PhysicalMemory + ?Sized>(
+        &self,
+        mem: &mut T,
+        vaddr: Address,
+    ) -> Result<PhysicalAddress> {
+        let index_bits = self.mode.index_bits();
+        let top_index_bits = self.mode.top_index_bits();
+        let pte_size = self.mode.pte_size();
+        let levels = self.mode.levels();
+
+        let oa_mask = (1u64 << self.mode.phys_bits()) - 1;
+
+        let mut table = self.dtb;
+        for level in (0..levels).rev() {
+            let width = if level == levels - 1 {
+                top_index_bits
+            } else {
+                index_bits
+            };
+            let shift = 12 + index_bits * level;
+            let index = (vaddr.as_u64() >> shift) & ((1u64 << width) - 1);
+            let pte_addr = table + (index as usize * pte_size);
+
+            let pte = self.read_pte(mem, pte_addr, pte_size)?;
+
+            // bit 0: present
+            if pte & 0b1 == 0 {
+                return Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds));
+            }
+
+            // bit 7 (PS) above the final level marks a large-page leaf
+            let is_large = level > 0 && pte & (1 << 7) != 0;
+
+            if level > 0 && !is_large {
+                // pointer to the next-level table, aligned to a 4 KiB page
+                table = Address::from(pte & oa_mask & !0xfffu64);
+                continue;
+            }
+
+            // leaf: compose the physical address from the descriptor base and
+            // the low `shift` bits of the virtual address
+            let base = pte & oa_mask & !((1u64 << shift) - 1);
+            let offset = vaddr.as_u64() & ((1u64 << shift) - 1);
+            return Ok(PhysicalAddress::from(base + offset));
+        }
+
+        Err(Error(ErrorOrigin::Mmu, ErrorKind::OutOfBounds))
+    }
+
+    /// Reads a 4- or 8-byte PTE (x86 page tables are always little-endian).
+    fn read_pte<T: PhysicalMemory + ?Sized>(
+        &self,
+        mem: &mut T,
+        addr: Address,
+        pte_size: usize,
+    ) -> Result<u64> {
+        let mut buf = [0u8; 8];
+        mem.phys_read_raw_into(addr.into(), &mut buf[..pte_size])?;
+        Ok(if pte_size == 4 {
+            let mut b = [0u8; 4];
+            b.copy_from_slice(&buf[..4]);
+            u32::from_le_bytes(b) as u64
+        } else {
+            u64::from_le_bytes(buf)
+        })
+    }
+}
+
+impl VirtualTranslate3 for X86VirtualTranslate {
+    fn virt_to_phys_iter<
+        T: PhysicalMemory + ?Sized,
+        B: SplitAtIndex,
+        VI: Iterator<Item = (Address, B)>,
+        VO: Extend<(PhysicalAddress, B)>,
+        FO: Extend<(Error, Address, B)>,
+    >(
+        &self,
+        mem: &mut T,
+        addrs: VI,
+        out: &mut VO,
+        out_fail: &mut FO,
+        _tmp_buf: &mut [std::mem::MaybeUninit<u8>],
+    ) {
+        for (addr, buf) in addrs {
+            match self.walk(mem, addr) {
+                Ok(paddr) => out.extend(Some((paddr, buf))),
+                Err(err) => out_fail.extend(Some((err, addr, buf))),
+            }
+        }
+    }
+
+    fn translation_table_id(&self, _address: Address) -> usize {
+        self.dtb.as_u64().overflowing_shr(12).0 as usize
+    }
+
+    fn arch(&self) -> ArchitectureObj {
+        match self.mode {
+            X86Mode::X32 => x32::ARCH,
+            X86Mode::X32Pae => x32_pae::ARCH,
+            X86Mode::X64 => x64::ARCH,
+            X86Mode::X64La57 => x64_la57::ARCH,
+        }
+    }
+}
+
+/// Creates a translator for the given `arch`, descending from the page table at
+/// `dtb` (the `CR3` root).
+pub fn new_translator(dtb: Address, arch: ArchitectureObj) -> Result<X86VirtualTranslate> {
+    let mode = match arch.ident() {
+        ArchitectureIdent::X86(32, false) => X86Mode::X32,
+        ArchitectureIdent::X86(32, true) => X86Mode::X32Pae,
+        ArchitectureIdent::X86(64, false) => X86Mode::X64,
+        ArchitectureIdent::X86(64, true) => X86Mode::X64La57,
+        _ => return Err(Error(ErrorOrigin::Mmu, ErrorKind::InvalidArchitecture)),
+    };
+    Ok(X86VirtualTranslate::new(mode, dtb))
+}
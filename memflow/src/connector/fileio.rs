@@ -120,12 +120,41 @@ impl Write for &CloneFile {
 pub struct FileIoMemory<T> {
     reader: T,
     mem_map: MemoryMap<(Address, usize)>,
+    sparse: bool,
 }
 
 impl<T: Seek + Read + Write + Send> FileIoMemory<T> {
     pub fn try_with_reader(reader: T, mem_map: MemoryMap<(Address, usize)>) -> Result<Self> {
-        Ok(Self { reader, mem_map })
+        Ok(Self {
+            reader,
+            mem_map,
+            sparse: false,
+        })
     }
+
+    /// Construct a memory backed by a *sparse* file.
+    ///
+    /// In sparse mode fully-zero write buffers that start at or past the end of
+    /// the file are not written out as literal zeros; instead the region is
+    /// left as a file hole, producing compact
+    /// memory dumps for unmapped guest RAM. Holes read back as zeros, so reads
+    /// remain correct and cheap.
+    pub fn try_with_reader_sparse(
+        reader: T,
+        mem_map: MemoryMap<(Address, usize)>,
+    ) -> Result<Self> {
+        Ok(Self {
+            reader,
+            mem_map,
+            sparse: true,
+        })
+    }
+}
+
+/// Returns `true` if every byte in `buf` is zero.
+#[inline]
+fn is_zero(buf: &[u8]) -> bool {
+    buf.iter().all(|&b| b == 0)
 }
 
 impl<T: Seek + Read + Write + Send> PhysicalMemory for FileIoMemory<T> {
@@ -141,9 +170,29 @@ impl<T: Seek + Read + Write + Send> PhysicalMemory for FileIoMemory<T> {
                 .map_err(|err| {
                     Error(ErrorOrigin::Connector, ErrorKind::UnableToSeekFile).log_error(err)
                 })?;
-            self.reader.read_exact(buf).map_err(|err| {
-                Error(ErrorOrigin::Connector, ErrorKind::UnableToWriteFile).log_error(err)
-            })?;
+
+            if self.sparse {
+                // a sparse dump omits trailing all-zero pages, so the backing
+                // file may be shorter than the mapped range; bytes past EOF are
+                // holes and read back as zeros rather than failing
+                let mut filled = 0;
+                while filled < buf.len() {
+                    match self.reader.read(&mut buf[filled..]) {
+                        Ok(0) => break,
+                        Ok(n) => filled += n,
+                        Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                        Err(err) => {
+                            return Err(Error(ErrorOrigin::Connector, ErrorKind::UnableToReadFile)
+                                .log_error(err))
+                        }
+                    }
+                }
+                buf[filled..].fill(0);
+            } else {
+                self.reader.read_exact(buf).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToWriteFile).log_error(err)
+                })?;
+            }
         }
         Ok(())
     }
@@ -154,6 +203,19 @@ impl<T: Seek + Read + Write + Send> PhysicalMemory for FileIoMemory<T> {
             .mem_map
             .map_iter(data.iter().copied().map(<_>::from), &mut void)
         {
+            // in sparse mode an all-zero buffer past the end of the file is left
+            // as a file hole: the target region is simply not written, reading
+            // back as zeros; inside the file the zeros are written so earlier
+            // data is overwritten
+            if self.sparse && is_zero(buf) {
+                let end = self.reader.seek(SeekFrom::End(0)).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToSeekFile).log_error(err)
+                })?;
+                if file_off.as_u64() >= end {
+                    continue;
+                }
+            }
+
             self.reader
                 .seek(SeekFrom::Start(file_off.as_u64()))
                 .map_err(|err| {
@@ -170,6 +232,7 @@ impl<T: Seek + Read + Write + Send> PhysicalMemory for FileIoMemory<T> {
         PhysicalMemoryMetadata {
             size: self.mem_map.max_address().as_usize(),
             readonly: false,
+            sparse: self.sparse,
         }
     }
 
@@ -185,3 +248,340 @@ cglue_impl_group!(
     ConnectorInstance,
     {}
 );
+
+/// Accesses physical memory via positioned file i/o (`pread`/`pwrite`).
+///
+/// This is a specialization of [`FileIoMemory`] for readers that implement
+/// [`FileExt`]. Instead of a `seek` followed by a `read`/`write` per mapped
+/// chunk (two syscalls per fragment), it folds the offset into the data syscall
+/// via `read_exact_at`/`write_all_at`. When `vectored` is enabled, chunks that
+/// are contiguous in the backing file are additionally coalesced so a large
+/// scattered `PhysicalReadData` list collapses into a handful of syscalls.
+#[derive(Clone)]
+pub struct PositionedFileIoMemory<T> {
+    reader: T,
+    mem_map: MemoryMap<(Address, usize)>,
+    vectored: bool,
+    sparse: bool,
+}
+
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
+#[cfg(windows)]
+use std::os::windows::fs::FileExt;
+
+#[cfg(any(unix, windows))]
+impl<T: FileExt + SparseFile + Send> PositionedFileIoMemory<T> {
+    pub fn try_with_reader(reader: T, mem_map: MemoryMap<(Address, usize)>) -> Result<Self> {
+        Ok(Self {
+            reader,
+            mem_map,
+            vectored: true,
+            sparse: false,
+        })
+    }
+
+    /// Enables hole-aware (sparse) reads and writes. Requires a file descriptor
+    /// that supports `fallocate`/`lseek(SEEK_HOLE)` (Linux).
+    pub fn sparse(mut self, sparse: bool) -> Self {
+        self.sparse = sparse;
+        self
+    }
+
+    /// Returns `true` if `[off, off + len)` is entirely a file hole, i.e. there
+    /// is no allocated data byte before the range ends. Non-sparse files (and
+    /// platforms without `SEEK_DATA`) never report holes, so reads fall through
+    /// to a normal positioned read.
+    fn is_hole(&self, off: u64, len: usize) -> bool {
+        match self.reader.next_data(off) {
+            // the next allocated byte is at or past the end of the range
+            Ok(Some(data_off)) => data_off >= off + len as u64,
+            // `SEEK_DATA` confirmed there is no allocated data through EOF
+            Ok(None) => true,
+            // detection failed/unavailable: fall through to a normal read rather
+            // than risk returning zeros in place of real data
+            Err(_) => false,
+        }
+    }
+
+    /// Enables (`true`, the default) or disables (`false`) the coalescing/vectored
+    /// path; when disabled, one positioned syscall is issued per mapped chunk.
+    pub fn vectored(mut self, vectored: bool) -> Self {
+        self.vectored = vectored;
+        self
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], off: u64) -> io::Result<()> {
+        self.reader.read_exact_at(buf, off)
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    fn write_at(&self, buf: &[u8], off: u64) -> io::Result<()> {
+        self.reader.write_all_at(buf, off)
+    }
+
+    #[cfg(windows)]
+    #[inline]
+    fn read_at(&self, mut buf: &mut [u8], mut off: u64) -> io::Result<()> {
+        while !buf.is_empty() {
+            let read = self.reader.seek_read(buf, off)?;
+            if read == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+            buf = &mut buf[read..];
+            off += read as u64;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    #[inline]
+    fn write_at(&self, mut buf: &[u8], mut off: u64) -> io::Result<()> {
+        while !buf.is_empty() {
+            let written = self.reader.seek_write(buf, off)?;
+            buf = &buf[written..];
+            off += written as u64;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(unix, windows))]
+impl<T: FileExt + SparseFile + Send> PhysicalMemory for PositionedFileIoMemory<T> {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        let mut void = FnExtend::void();
+
+        // collect the mapped chunks so we can coalesce file-contiguous runs
+        let mut chunks: Vec<(u64, &mut [u8])> = Vec::with_capacity(data.len());
+        for ((file_off, _), buf) in self.mem_map.map_iter(
+            data.iter_mut()
+                .map(|PhysicalReadData(addr, buf)| (*addr, &mut **buf)),
+            &mut void,
+        ) {
+            chunks.push((file_off.as_u64(), buf));
+        }
+
+        // in sparse mode holes are detected via `lseek(SEEK_DATA)` and returned
+        // as zeroed buffers without touching the disk, so coalescing is bypassed
+        if self.sparse {
+            for (off, buf) in chunks {
+                if self.is_hole(off, buf.len()) {
+                    buf.fill(0);
+                    continue;
+                }
+                self.read_at(buf, off).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToReadFile).log_error(err)
+                })?;
+            }
+            return Ok(());
+        }
+
+        if !self.vectored {
+            for (off, buf) in chunks {
+                self.read_at(buf, off).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToReadFile).log_error(err)
+                })?;
+            }
+            return Ok(());
+        }
+
+        // coalesce contiguous runs into a single positioned read, then scatter
+        // the result back into the individual target buffers
+        let mut i = 0;
+        while i < chunks.len() {
+            let start_off = chunks[i].0;
+            let mut total = chunks[i].1.len();
+            let mut j = i + 1;
+            while j < chunks.len() && chunks[j].0 == start_off + total as u64 {
+                total += chunks[j].1.len();
+                j += 1;
+            }
+
+            if j == i + 1 {
+                let (off, ref mut buf) = chunks[i];
+                self.read_at(buf, off).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToReadFile).log_error(err)
+                })?;
+            } else {
+                let mut scratch = vec![0u8; total];
+                self.read_at(&mut scratch, start_off).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToReadFile).log_error(err)
+                })?;
+                let mut pos = 0;
+                for (_, buf) in chunks[i..j].iter_mut() {
+                    let len = buf.len();
+                    buf.copy_from_slice(&scratch[pos..pos + len]);
+                    pos += len;
+                }
+            }
+
+            i = j;
+        }
+
+        Ok(())
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        let mut void = FnExtend::void();
+
+        let mut chunks: Vec<(u64, &[u8])> = Vec::with_capacity(data.len());
+        for ((file_off, _), buf) in self
+            .mem_map
+            .map_iter(data.iter().copied().map(<_>::from), &mut void)
+        {
+            chunks.push((file_off.as_u64(), buf));
+        }
+
+        if !self.vectored {
+            for (off, buf) in chunks {
+                if self.sparse && is_zero(buf) {
+                    self.reader.punch_hole(off, buf.len() as u64).map_err(|err| {
+                        Error(ErrorOrigin::Connector, ErrorKind::UnableToWriteFile).log_error(err)
+                    })?;
+                    continue;
+                }
+                self.write_at(buf, off).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToWriteFile).log_error(err)
+                })?;
+            }
+            return Ok(());
+        }
+
+        let mut i = 0;
+        while i < chunks.len() {
+            let start_off = chunks[i].0;
+            let mut total = chunks[i].1.len();
+            let mut j = i + 1;
+            while j < chunks.len() && chunks[j].0 == start_off + total as u64 {
+                total += chunks[j].1.len();
+                j += 1;
+            }
+
+            if self.sparse && chunks[i..j].iter().all(|(_, b)| is_zero(b)) {
+                self.reader.punch_hole(start_off, total as u64).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToWriteFile).log_error(err)
+                })?;
+                i = j;
+                continue;
+            }
+
+            if j == i + 1 {
+                let (off, buf) = chunks[i];
+                self.write_at(buf, off).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToWriteFile).log_error(err)
+                })?;
+            } else {
+                let mut scratch = Vec::with_capacity(total);
+                for (_, buf) in &chunks[i..j] {
+                    scratch.extend_from_slice(buf);
+                }
+                self.write_at(&scratch, start_off).map_err(|err| {
+                    Error(ErrorOrigin::Connector, ErrorKind::UnableToWriteFile).log_error(err)
+                })?;
+            }
+
+            i = j;
+        }
+
+        Ok(())
+    }
+
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        PhysicalMemoryMetadata {
+            size: self.mem_map.max_address().as_usize(),
+            readonly: false,
+            sparse: self.sparse,
+        }
+    }
+
+    #[inline]
+    fn set_mem_map(&mut self, mem_map: &[PhysicalMemoryMapping]) {
+        let map = MemoryMap::<(Address, usize)>::from_vec(mem_map.to_vec());
+        self.mem_map.merge(map);
+    }
+}
+
+/// Platform abstraction for the sparse-file operations used by the hole-aware
+/// read/write paths.
+///
+/// On Linux these map directly onto `fallocate(FALLOC_FL_PUNCH_HOLE)` and
+/// `lseek(SEEK_DATA)`. On platforms lacking those primitives the operations
+/// degrade gracefully: punching a hole becomes a no-op (the caller simply does
+/// not write the zeros) and no hole is ever reported on reads.
+#[cfg(any(unix, windows))]
+pub trait SparseFile {
+    /// Reclaim `[off, off + len)` as a hole, freeing any blocks already on disk.
+    fn punch_hole(&self, off: u64, len: u64) -> io::Result<()>;
+
+    /// Offset of the next allocated byte at or after `off`, or `None` if the
+    /// rest of the file is a hole.
+    fn next_data(&self, off: u64) -> io::Result<Option<u64>>;
+}
+
+#[cfg(target_os = "linux")]
+impl<T: std::os::unix::io::AsRawFd> SparseFile for T {
+    fn punch_hole(&self, off: u64, len: u64) -> io::Result<()> {
+        // FALLOC_FL_KEEP_SIZE = 0x01, FALLOC_FL_PUNCH_HOLE = 0x02
+        let ret = unsafe {
+            libc::fallocate(
+                self.as_raw_fd(),
+                0x01 | 0x02,
+                off as libc::off_t,
+                len as libc::off_t,
+            )
+        };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    fn next_data(&self, off: u64) -> io::Result<Option<u64>> {
+        // SEEK_DATA = 3
+        let ret = unsafe { libc::lseek(self.as_raw_fd(), off as libc::off_t, 3) };
+        if ret < 0 {
+            let err = io::Error::last_os_error();
+            // ENXIO indicates there is no data past `off`: the tail is a hole
+            if err.raw_os_error() == Some(libc::ENXIO) {
+                Ok(None)
+            } else {
+                Err(err)
+            }
+        } else {
+            Ok(Some(ret as u64))
+        }
+    }
+}
+
+#[cfg(all(any(unix, windows), not(target_os = "linux")))]
+impl<T> SparseFile for T {
+    fn punch_hole(&self, _off: u64, _len: u64) -> io::Result<()> {
+        // without an explicit punch syscall, leaving the range unwritten on a
+        // sparse/truncated file already reads back as zeros
+        Ok(())
+    }
+
+    fn next_data(&self, off: u64) -> io::Result<Option<u64>> {
+        // no `SEEK_DATA` here: report data present at `off` so hole detection
+        // never fires and reads fall through to a normal positioned read
+        Ok(Some(off))
+    }
+}
+
+impl<T: Seek + Read + Write + Send> FileIoMemory<T> {
+    /// Construct a positioned-i/o backed memory from a [`FileExt`] reader.
+    ///
+    /// Prefer this over [`try_with_reader`](Self::try_with_reader) when the
+    /// reader is a real file: it avoids a `seek` syscall per mapped chunk.
+    #[cfg(any(unix, windows))]
+    pub fn with_positioned_reader<F: FileExt + SparseFile + Send>(
+        reader: F,
+        mem_map: MemoryMap<(Address, usize)>,
+    ) -> Result<PositionedFileIoMemory<F>> {
+        PositionedFileIoMemory::try_with_reader(reader, mem_map)
+    }
+}